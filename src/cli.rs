@@ -0,0 +1,342 @@
+use std::path::PathBuf;
+
+use virtual_memory::{
+    geometry::Geometry,
+    vm::{
+        allocator::{self, FIT_NAMES},
+        replacement::{self, POLICY_NAMES},
+        scheme,
+        tlb::{TlbConfig, TlbReplacement},
+    },
+};
+
+use crate::process::{Options, OutputFormat, STDIO_PATH};
+
+pub const USAGE: &str = "\
+Usage: virtual-memory [repl|validate] [OPTIONS]
+
+Translates every virtual address in the input file using the segment and
+page tables described by the init file. Use - as a path for stdin or stdout.
+
+With repl, loads the init file and reads commands from stdin instead. Type
+help at the prompt for the list of commands.
+
+With validate, checks the init file for segments sharing a page table, pages
+colliding with page tables or each other, pages of undeclared segments and
+segments larger than their page table, and exits with status 1 if any exist.
+
+Options:
+  -i, --init <PATH>       Init file [default: init-dp.txt]
+  -a, --input <PATH>      Input addresses [default: input-dp.txt]
+  -o, --output <PATH>     Output file [default: output-dp.txt]
+  -f, --format <FORMAT>   Output format: text, lines, json or jsonl [default: text]
+  -s, --stats <PATH>      Also write a summary of faults and allocations
+  -r, --trace <PATH>      Also write how each address was translated
+  -S, --snapshot <PATH>   Save the final state as a snapshot
+  -R, --restore <PATH>    Start from a snapshot instead of the init file
+  -p, --policy <POLICY>   Page replacement: fifo, lru or clock [default: fifo]
+  -t, --tlb <E:W:R>       TLB with E entries, W ways and fifo or lru replacement
+  -z, --demand-zero       Map zeroed pages for unmapped addresses inside a segment
+  -P, --paged-segment-table
+                          Page segment tables behind a resident directory
+  -m, --scheme <SCHEME>   Translation: segmented, radix:N for N levels of page
+                          tables over s and p, inverted for a hashed
+                          inverted page table, pure-segmentation or
+                          pure-paging [default: segmented]
+  -F, --fit <FIT>         Holes pure segmentation loads segments into: first,
+                          best, worst or next [default: first]
+  -g, --geometry <S:P:W:F>
+                          Segment, page and offset bits and frame count [default: 9:9:9:1024]
+  -h, --help              Print this message";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Command {
+    /// Translate the input file into the output file.
+    #[default]
+    Process,
+    /// Explore the address space interactively.
+    Repl,
+    /// Report conflicts in the init file.
+    Validate,
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub command: Command,
+    pub init: PathBuf,
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub options: Options,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            command: Command::default(),
+            init: "init-dp.txt".into(),
+            input: "input-dp.txt".into(),
+            output: "output-dp.txt".into(),
+            options: Options::default(),
+            help: false,
+        }
+    }
+}
+
+/// Splits `value` on `:` into exactly `N` numbers.
+fn parse_fields<const N: usize>(flag: &str, value: &str) -> Result<[usize; N], String> {
+    let fields: Vec<&str> = value.split(':').collect();
+    let invalid = || format!("invalid value '{value}' for {flag}");
+
+    if fields.len() != N {
+        return Err(invalid());
+    }
+
+    let mut numbers = [0; N];
+    for (number, field) in numbers.iter_mut().zip(fields) {
+        *number = field.parse().map_err(|_| invalid())?;
+    }
+
+    Ok(numbers)
+}
+
+fn parse_tlb(value: &str) -> Result<TlbConfig, String> {
+    let invalid = || format!("invalid value '{value}' for --tlb");
+    let (sizes, replacement) = value.rsplit_once(':').ok_or_else(invalid)?;
+    let [entries, associativity] = parse_fields("--tlb", sizes)?;
+
+    let replacement = match replacement {
+        "fifo" => TlbReplacement::Fifo,
+        "lru" => TlbReplacement::Lru,
+        _ => return Err(invalid()),
+    };
+
+    let config = TlbConfig {
+        entries,
+        associativity,
+        replacement,
+    };
+    config.validate().map_err(|_| invalid())?;
+
+    Ok(config)
+}
+
+fn parse_geometry(value: &str) -> Result<Geometry, String> {
+    let [segment_bits, page_bits, offset_bits, frame_count] = parse_fields("--geometry", value)?;
+
+    Geometry::new(segment_bits, page_bits, offset_bits, frame_count)
+        .map_err(|_| format!("invalid value '{value}' for --geometry"))
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().peekable();
+
+        match args.peek().map(String::as_str) {
+            Some("repl") => parsed.command = Command::Repl,
+            Some("validate") => parsed.command = Command::Validate,
+            _ => {}
+        }
+        if parsed.command != Command::Process {
+            args.next();
+        }
+
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {flag}"))
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "-i" | "--init" => parsed.init = value()?.into(),
+                "-a" | "--input" => parsed.input = value()?.into(),
+                "-o" | "--output" => parsed.output = value()?.into(),
+                "-f" | "--format" => {
+                    parsed.options.format = match value()?.as_str() {
+                        "text" => OutputFormat::Text,
+                        "lines" => OutputFormat::Lines,
+                        "json" => OutputFormat::Json,
+                        "jsonl" => OutputFormat::JsonLines,
+                        value => return Err(format!("invalid value '{value}' for {flag}")),
+                    };
+                }
+                "-s" | "--stats" => parsed.options.stats = Some(value()?.into()),
+                "-r" | "--trace" => parsed.options.trace = Some(value()?.into()),
+                "-S" | "--snapshot" => parsed.options.snapshot = Some(value()?.into()),
+                "-R" | "--restore" => parsed.options.restore = Some(value()?.into()),
+                "-p" | "--policy" => {
+                    let value = value()?;
+                    parsed.options.policy =
+                        Some(replacement::from_name(&value).ok_or_else(|| {
+                            format!(
+                                "invalid value '{value}' for {flag}, expected one of {}",
+                                POLICY_NAMES.join(", ")
+                            )
+                        })?);
+                }
+                "-F" | "--fit" => {
+                    let value = value()?;
+                    parsed.options.fit = Some(allocator::from_name(&value).ok_or_else(|| {
+                        format!(
+                            "invalid value '{value}' for {flag}, expected one of {}",
+                            FIT_NAMES.join(", ")
+                        )
+                    })?);
+                }
+                "-z" | "--demand-zero" => parsed.options.demand_zero = true,
+                "-P" | "--paged-segment-table" => parsed.options.paged_segment_table = true,
+                "-m" | "--scheme" => {
+                    let value = value()?;
+                    parsed.options.scheme = scheme::from_name(&value)
+                        .ok_or_else(|| format!("invalid value '{value}' for {flag}"))?;
+                }
+                "-t" | "--tlb" => parsed.options.tlb = Some(parse_tlb(&value()?)?),
+                "-g" | "--geometry" => parsed.options.geometry = parse_geometry(&value()?)?,
+                _ => return Err(format!("unexpected argument '{flag}'")),
+            }
+        }
+
+        // The snapshot replaces the init file when both are given
+        let state = parsed.options.restore.as_ref().unwrap_or(&parsed.init);
+        let input_from_stdin = match parsed.command {
+            Command::Process => parsed.input.as_os_str() == STDIO_PATH,
+            Command::Repl => true,
+            Command::Validate => false,
+        };
+        if state.as_os_str() == STDIO_PATH && input_from_stdin {
+            return Err("init and input cannot both be read from stdin".into());
+        }
+
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_memory::vm::{allocator::Fit, scheme::Scheme};
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn defaults() {
+        let args = parse(&[]).expect("Failed to parse args");
+
+        assert_eq!(args.init, PathBuf::from("init-dp.txt"));
+        assert_eq!(args.input, PathBuf::from("input-dp.txt"));
+        assert_eq!(args.output, PathBuf::from("output-dp.txt"));
+        assert_eq!(args.options.format, OutputFormat::Text);
+        assert!(args.options.policy.is_none());
+        assert!(args.options.fit.is_none());
+        assert!(args.options.stats.is_none());
+        assert!(!args.options.demand_zero);
+        assert!(!args.options.paged_segment_table);
+        assert_eq!(args.options.scheme, Scheme::Segmented);
+        assert!(!args.help);
+        assert_eq!(args.command, Command::Process);
+    }
+
+    #[test]
+    fn repl() {
+        let args = parse(&["repl", "-i", "init.txt"]).expect("Failed to parse args");
+        assert_eq!(args.command, Command::Repl);
+        assert_eq!(args.init, PathBuf::from("init.txt"));
+
+        assert!(parse(&["repl", "-i", "-"]).is_err());
+        assert!(parse(&["-i", "init.txt", "repl"]).is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let args = parse(&["validate", "-i", "-"]).expect("Failed to parse args");
+        assert_eq!(args.command, Command::Validate);
+        assert_eq!(args.init, PathBuf::from("-"));
+    }
+
+    #[test]
+    fn all_options() {
+        let args = parse(&[
+            "--init",
+            "a.txt",
+            "-a",
+            "-",
+            "-o",
+            "-",
+            "-f",
+            "lines",
+            "-s",
+            "stats.txt",
+            "-r",
+            "trace.txt",
+            "-S",
+            "snapshot.txt",
+            "-p",
+            "clock",
+            "-t",
+            "16:4:lru",
+            "-F",
+            "best",
+            "-z",
+            "-P",
+            "-m",
+            "radix:3",
+            "-g",
+            "10:10:12:64",
+        ])
+        .expect("Failed to parse args");
+
+        assert_eq!(args.init, PathBuf::from("a.txt"));
+        assert_eq!(args.input, PathBuf::from("-"));
+        assert_eq!(args.output, PathBuf::from("-"));
+        assert_eq!(args.options.format, OutputFormat::Lines);
+        assert_eq!(args.options.stats, Some(PathBuf::from("stats.txt")));
+        assert_eq!(args.options.trace, Some(PathBuf::from("trace.txt")));
+        assert_eq!(args.options.snapshot, Some(PathBuf::from("snapshot.txt")));
+        assert_eq!(
+            args.options.policy.map(|policy| policy.name()),
+            Some("clock")
+        );
+        assert_eq!(
+            args.options.tlb,
+            Some(TlbConfig {
+                entries: 16,
+                associativity: 4,
+                replacement: TlbReplacement::Lru
+            })
+        );
+        assert_eq!(args.options.fit, Some(Fit::Best));
+        assert!(args.options.demand_zero);
+        assert!(args.options.paged_segment_table);
+        assert_eq!(args.options.scheme, Scheme::Radix { levels: 3 });
+        assert_eq!(args.options.geometry.page_size(), 4096);
+    }
+
+    #[test]
+    fn help() {
+        let args = parse(&["-h"]).expect("Failed to parse args");
+        assert!(args.help);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(parse(&["--init"]).is_err());
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["-f", "xml"]).is_err());
+        assert!(parse(&["-f", "jsonl"]).is_ok());
+        assert!(parse(&["-p", "random"]).is_err());
+        assert!(parse(&["-F", "random"]).is_err());
+        assert!(parse(&["-t", "16:4"]).is_err());
+        assert!(parse(&["-t", "6:4:lru"]).is_err());
+        assert!(parse(&["-t", "0:0:fifo"]).is_err());
+        assert!(parse(&["-t", "8:4:lru"]).is_ok());
+        assert!(parse(&["-g", "9:10:9:1024"]).is_err());
+        assert!(parse(&["-i", "-", "-a", "-"]).is_err());
+        assert!(parse(&["-R", "-", "-a", "-"]).is_err());
+        assert!(parse(&["-i", "-", "-R", "state.txt", "-a", "-"]).is_ok());
+    }
+}
@@ -1,16 +1,21 @@
+// Default address geometry, see `Geometry`
 pub const SEGMENT_SIZE_BITS: usize = 9;
 pub const PAGE_SIZE_BITS: usize = 9;
-pub const FRAME_SIZE_BITS: usize = 9;
+pub const FRAME_COUNT: usize = 1024;
+
+/// Widest segment, page or offset field, so each fits in a `u16`.
+pub const WORD_BITS: usize = 16;
+
+/// Most words of physical memory, and so of disk, a geometry may ask for.
+pub const MAX_WORDS: usize = 1 << 24;
 
-pub const PAGE_SIZE: usize = 512;
-pub const PAGE_COUNT: usize = 1024;
 pub const SEGMENT_WORD_COUNT: usize = 2;
 pub const SEGMENT_SIZE_OFFSET: usize = 0;
 pub const SEGMENT_PAGE_TABLE_OFFSET: usize = 1;
+/// Pure segmentation keeps the segment's base address in place of its page table.
+pub const SEGMENT_BASE_OFFSET: usize = 1;
 
-pub const MAX_SEGMENT_OFFSET: u16 = (1 << SEGMENT_SIZE_BITS) - 1;
-pub const MAX_SEGMENT_SIZE: u32 =
-    (PAGE_SIZE * PAGE_COUNT - ((1 << SEGMENT_SIZE_BITS) * SEGMENT_WORD_COUNT)) as u32;
-pub const MIN_POSITIVE_PAGE_OFFSET: i16 =
-    (((1 << SEGMENT_SIZE_BITS) * SEGMENT_WORD_COUNT) / PAGE_SIZE) as i16;
-pub const MAX_PAGE_OFFSET: i16 = PAGE_COUNT as i16 - 1;
+pub const INVERTED_WORD_COUNT: usize = 3;
+pub const INVERTED_PROCESS_OFFSET: usize = 0;
+pub const INVERTED_PAGE_OFFSET: usize = 1;
+pub const INVERTED_NEXT_OFFSET: usize = 2;
@@ -1,34 +1,311 @@
+use std::{error::Error, fmt, io, num::TryFromIntError, path::PathBuf};
+
+use crate::io::validate::Diagnostic;
+
 #[derive(Debug, PartialEq, Eq)]
 #[allow(clippy::module_name_repetitions)]
 pub enum VMError {
     MemoryFull,
+    /// Every disk block is in use, so an evicted page has nowhere to go.
+    DiskFull,
     InvalidSegment,
     InvalidSegmentSize,
     InvalidFrame,
+    InvalidGeometry,
     InvalidPage,
+    InvalidProcess,
+    InvalidProtection,
+    InvalidTlbConfig,
+    InvalidSnapshot,
+    InvalidAddress,
+    /// A line of an init file whose tokens do not divide into triples.
+    IncompleteTriple,
+    /// Triples of an init file that conflict with each other.
+    InitConflict(Diagnostic),
+    SegmentExists,
+    SegmentNotFound,
     VirtualAddressLeadingBits,
     VirtualAddressOutOfBounds,
     MemoryNotInitialized,
-    TryFromIntError(String),
-    IOError(String),
+    ProtectionFault,
+    /// An operation on segment tables while translating without them.
+    UnsupportedScheme,
+    /// `error` at the 1-based `line` and `token` of `file`.
+    Parse {
+        file: PathBuf,
+        line: usize,
+        token: usize,
+        error: Box<Self>,
+    },
+    /// `error` while reading or writing `path`.
+    File {
+        path: PathBuf,
+        error: Box<Self>,
+    },
+    /// `error` while translating the virtual `address`.
+    Address {
+        address: u32,
+        error: Box<Self>,
+    },
+    TryFromIntError(TryFromIntError),
+    IOError(IoError),
 }
 
-impl From<std::num::TryFromIntError> for VMError {
-    fn from(error: std::num::TryFromIntError) -> Self {
-        Self::TryFromIntError(error.to_string())
+impl VMError {
+    /// Stable `snake_case` name of the error, for machine readable output.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::MemoryFull => "memory_full",
+            Self::DiskFull => "disk_full",
+            Self::InvalidSegment => "invalid_segment",
+            Self::InvalidSegmentSize => "invalid_segment_size",
+            Self::InvalidFrame => "invalid_frame",
+            Self::InvalidGeometry => "invalid_geometry",
+            Self::InvalidPage => "invalid_page",
+            Self::InvalidProcess => "invalid_process",
+            Self::InvalidProtection => "invalid_protection",
+            Self::InvalidTlbConfig => "invalid_tlb_config",
+            Self::InvalidSnapshot => "invalid_snapshot",
+            Self::InvalidAddress => "invalid_address",
+            Self::IncompleteTriple => "incomplete_triple",
+            Self::InitConflict(_) => "init_conflict",
+            Self::SegmentExists => "segment_exists",
+            Self::SegmentNotFound => "segment_not_found",
+            Self::VirtualAddressLeadingBits => "virtual_address_leading_bits",
+            Self::VirtualAddressOutOfBounds => "virtual_address_out_of_bounds",
+            Self::MemoryNotInitialized => "memory_not_initialized",
+            Self::ProtectionFault => "protection_fault",
+            Self::UnsupportedScheme => "unsupported_scheme",
+            Self::Parse { .. } => "parse_error",
+            Self::File { .. } => "file_error",
+            Self::Address { .. } => "address_error",
+            Self::TryFromIntError(_) => "try_from_int_error",
+            Self::IOError(_) => "io_error",
+        }
+    }
+
+    /// Innermost error, skipping the file, address and position context.
+    #[must_use]
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Parse { error, .. } | Self::File { error, .. } | Self::Address { error, .. } => {
+                error.root()
+            }
+            error => error,
+        }
+    }
+
+    /// Wraps `self` with the file it came from, or fills in the file of a
+    /// `Parse` error raised without one.
+    #[must_use]
+    pub fn in_file(self, path: impl Into<PathBuf>) -> Self {
+        match self {
+            Self::Parse {
+                file,
+                line,
+                token,
+                error,
+            } if file.as_os_str().is_empty() => Self::Parse {
+                file: path.into(),
+                line,
+                token,
+                error,
+            },
+            error => Self::File {
+                path: path.into(),
+                error: Box::new(error),
+            },
+        }
+    }
+}
+
+impl fmt::Display for VMError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MemoryFull => write!(f, "physical memory is full"),
+            Self::DiskFull => write!(f, "no free disk blocks"),
+            Self::InvalidSegment => write!(f, "invalid segment table entry"),
+            Self::InvalidSegmentSize => write!(f, "invalid segment size"),
+            Self::InvalidFrame => write!(f, "invalid frame number"),
+            Self::InvalidGeometry => write!(f, "invalid address geometry"),
+            Self::InvalidPage => write!(f, "invalid page table entry"),
+            Self::InvalidProcess => write!(f, "invalid process id"),
+            Self::InvalidProtection => write!(f, "invalid protection"),
+            Self::InvalidTlbConfig => write!(f, "invalid TLB configuration"),
+            Self::InvalidSnapshot => write!(f, "invalid snapshot"),
+            Self::InvalidAddress => write!(f, "invalid address"),
+            Self::IncompleteTriple => write!(f, "incomplete triple"),
+            Self::InitConflict(diagnostic) => write!(f, "{diagnostic}"),
+            Self::SegmentExists => write!(f, "segment already exists"),
+            Self::SegmentNotFound => write!(f, "segment not found"),
+            Self::VirtualAddressLeadingBits => {
+                write!(f, "virtual address has bits set above the segment number")
+            }
+            Self::VirtualAddressOutOfBounds => write!(f, "virtual address is out of bounds"),
+            Self::MemoryNotInitialized => write!(f, "memory is not initialized"),
+            Self::ProtectionFault => write!(f, "protection fault"),
+            Self::UnsupportedScheme => write!(f, "not supported by the translation scheme"),
+            Self::Parse {
+                file, line, token, ..
+            } => write!(f, "{}:{line}: token {token}", file.display()),
+            Self::File { path, .. } => write!(f, "{}", path.display()),
+            Self::Address { address, .. } => write!(f, "address {address}"),
+            Self::TryFromIntError(error) => write!(f, "{error}"),
+            Self::IOError(_) => write!(f, "I/O error"),
+        }
+    }
+}
+
+impl Error for VMError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Parse { error, .. } | Self::File { error, .. } | Self::Address { error, .. } => {
+                Some(error.as_ref())
+            }
+            Self::TryFromIntError(error) => error.source(),
+            Self::IOError(error) => Some(&error.0),
+            _ => None,
+        }
+    }
+}
+
+/// An [`io::Error`] that compares equal to another of the same kind and message.
+#[derive(Debug)]
+pub struct IoError(pub io::Error);
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.kind() == other.0.kind() && self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl Eq for IoError {}
+
+impl From<TryFromIntError> for VMError {
+    fn from(error: TryFromIntError) -> Self {
+        Self::TryFromIntError(error)
     }
 }
 
 impl From<std::convert::Infallible> for VMError {
     fn from(error: std::convert::Infallible) -> Self {
-        Self::TryFromIntError(error.to_string())
+        match error {}
     }
 }
 
-impl From<std::io::Error> for VMError {
-    fn from(error: std::io::Error) -> Self {
-        Self::IOError(error.to_string())
+impl From<io::Error> for VMError {
+    fn from(error: io::Error) -> Self {
+        Self::IOError(IoError(error))
     }
 }
 
+/// Formats `error` followed by each of its sources, separated by `: `.
+#[must_use]
+pub fn display_chain(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    message
+}
+
 pub type VMResult<T> = Result<T, VMError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code() {
+        assert_eq!(
+            VMError::VirtualAddressOutOfBounds.code(),
+            "virtual_address_out_of_bounds"
+        );
+        assert_eq!(
+            VMError::from(io::Error::from(io::ErrorKind::NotFound)).code(),
+            "io_error"
+        );
+    }
+
+    #[test]
+    fn source_chain() {
+        let error = VMError::Parse {
+            file: "init.txt".into(),
+            line: 2,
+            token: 5,
+            error: Box::new(VMError::InvalidPage),
+        };
+
+        assert_eq!(error.root(), &VMError::InvalidPage);
+        assert_eq!(
+            error.source().map(ToString::to_string),
+            Some("invalid page table entry".to_string())
+        );
+        assert_eq!(
+            display_chain(&error),
+            "init.txt:2: token 5: invalid page table entry"
+        );
+
+        let error =
+            VMError::from(io::Error::new(io::ErrorKind::NotFound, "missing")).in_file("a.txt");
+        assert_eq!(display_chain(&error), "a.txt: I/O error: missing");
+        assert!(matches!(
+            error.root(),
+            VMError::IOError(IoError(io_error)) if io_error.kind() == io::ErrorKind::NotFound
+        ));
+
+        let error = VMError::Parse {
+            file: PathBuf::new(),
+            line: 4,
+            token: 3,
+            error: Box::new(VMError::InvalidFrame),
+        }
+        .in_file("snapshot.txt");
+        assert_eq!(
+            display_chain(&error),
+            "snapshot.txt:4: token 3: invalid frame number"
+        );
+    }
+
+    #[test]
+    fn nested_context() {
+        let error = VMError::Address {
+            address: 2_097_162,
+            error: Box::new(VMError::MemoryFull),
+        }
+        .in_file("input.txt");
+
+        assert_eq!(error.code(), "file_error");
+        assert_eq!(error.root(), &VMError::MemoryFull);
+        assert_eq!(error.root().code(), "memory_full");
+        assert_eq!(
+            display_chain(&error),
+            "input.txt: address 2097162: physical memory is full"
+        );
+
+        // Errors without context have no source
+        assert!(VMError::MemoryFull.source().is_none());
+        assert_eq!(
+            display_chain(&VMError::MemoryFull),
+            "physical memory is full"
+        );
+    }
+
+    #[test]
+    fn try_from_int() {
+        let error = VMError::from(u8::try_from(300).expect_err("300 fits in a u8"));
+
+        assert_eq!(error.code(), "try_from_int_error");
+        assert_eq!(error.root(), &error);
+        assert_eq!(
+            error.to_string(),
+            "out of range integral type conversion attempted"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{
+    constants::{
+        FRAME_COUNT, MAX_WORDS, PAGE_SIZE_BITS, SEGMENT_SIZE_BITS, SEGMENT_WORD_COUNT, WORD_BITS,
+    },
+    error::{VMError, VMResult},
+};
+
+/// Shape of the virtual address space and physical memory.
+///
+/// A virtual address is `segment_bits` of segment number `s`, followed by
+/// `page_bits` of page number `p` and `offset_bits` of word offset `w`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Geometry {
+    segment_bits: usize,
+    page_bits: usize,
+    offset_bits: usize,
+    frame_count: usize,
+}
+
+impl Geometry {
+    /// Creates a new `Geometry`.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidGeometry` if the address does not fit in 32 bits, a
+    ///   field is wider than 16 bits, a page table does not fit in one frame,
+    ///   there are not enough frames for the segment table and one page, or
+    ///   memory would hold more than `MAX_WORDS` words.
+    pub fn new(
+        segment_bits: usize,
+        page_bits: usize,
+        offset_bits: usize,
+        frame_count: usize,
+    ) -> VMResult<Self> {
+        let geometry = Self {
+            segment_bits,
+            page_bits,
+            offset_bits,
+            frame_count,
+        };
+
+        let field_bits = 1..=WORD_BITS;
+        if !field_bits.contains(&segment_bits)
+            || !field_bits.contains(&page_bits)
+            || !field_bits.contains(&offset_bits)
+            || segment_bits + page_bits + offset_bits > 32
+            || page_bits > offset_bits
+            || frame_count <= geometry.segment_table_frames()
+            || frame_count > usize::from(u16::MAX >> 1) + 1
+            || frame_count * geometry.page_size() > MAX_WORDS
+        {
+            return Err(VMError::InvalidGeometry);
+        }
+
+        Ok(geometry)
+    }
+
+    #[must_use]
+    pub const fn segment_bits(&self) -> usize {
+        self.segment_bits
+    }
+
+    #[must_use]
+    pub const fn page_bits(&self) -> usize {
+        self.page_bits
+    }
+
+    #[must_use]
+    pub const fn offset_bits(&self) -> usize {
+        self.offset_bits
+    }
+
+    /// Number of physical frames, which is also the number of disk blocks.
+    #[must_use]
+    pub const fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// Words per page and frame.
+    #[must_use]
+    pub const fn page_size(&self) -> usize {
+        1 << self.offset_bits
+    }
+
+    #[must_use]
+    pub const fn segment_count(&self) -> usize {
+        1 << self.segment_bits
+    }
+
+    #[must_use]
+    pub const fn pages_per_segment(&self) -> usize {
+        1 << self.page_bits
+    }
+
+    /// Frames needed to hold one segment table.
+    #[must_use]
+    pub const fn segment_table_frames(&self) -> usize {
+        (self.segment_count() * SEGMENT_WORD_COUNT).div_ceil(self.page_size())
+    }
+
+    #[must_use]
+    pub const fn address_bits(&self) -> usize {
+        self.segment_bits + self.page_bits + self.offset_bits
+    }
+
+    #[must_use]
+    pub const fn max_segment(&self) -> usize {
+        self.segment_count() - 1
+    }
+
+    #[must_use]
+    pub const fn max_page(&self) -> usize {
+        self.pages_per_segment() - 1
+    }
+
+    /// Largest segment size accepted in an init file: all of physical memory
+    /// except one segment table.
+    #[must_use]
+    pub const fn max_segment_size(&self) -> usize {
+        self.page_size() * self.frame_count - self.segment_count() * SEGMENT_WORD_COUNT
+    }
+
+    /// Smallest frame a page or page table may occupy, since the first frames
+    /// hold the segment table.
+    #[must_use]
+    pub const fn min_frame(&self) -> usize {
+        self.segment_table_frames()
+    }
+
+    #[must_use]
+    pub const fn max_frame(&self) -> usize {
+        self.frame_count - 1
+    }
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        Self {
+            segment_bits: SEGMENT_SIZE_BITS,
+            page_bits: PAGE_SIZE_BITS,
+            offset_bits: PAGE_SIZE_BITS,
+            frame_count: FRAME_COUNT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default() {
+        let geometry = Geometry::default();
+
+        assert_eq!(Geometry::new(9, 9, 9, 1024), Ok(geometry));
+        assert_eq!(geometry.page_size(), 512);
+        assert_eq!(geometry.segment_table_frames(), 2);
+        assert_eq!(geometry.address_bits(), 27);
+        assert_eq!(geometry.max_segment(), 511);
+        assert_eq!(geometry.max_page(), 511);
+        assert_eq!(geometry.max_segment_size(), 523_264);
+        assert_eq!(geometry.min_frame(), 2);
+        assert_eq!(geometry.max_frame(), 1023);
+    }
+
+    #[test]
+    fn large_pages() {
+        let geometry = Geometry::new(8, 10, 12, 64).expect("Failed to create Geometry");
+
+        assert_eq!(geometry.page_size(), 4096);
+        assert_eq!(geometry.segment_table_frames(), 1);
+        assert_eq!(geometry.max_page(), 1023);
+    }
+
+    #[test]
+    fn full_address_space() {
+        let geometry = Geometry::new(10, 10, 12, 64).expect("Failed to create Geometry");
+        assert_eq!(geometry.address_bits(), 32);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(Geometry::new(11, 10, 12, 64), Err(VMError::InvalidGeometry));
+        assert_eq!(Geometry::new(9, 10, 9, 1024), Err(VMError::InvalidGeometry));
+        assert_eq!(Geometry::new(9, 9, 9, 2), Err(VMError::InvalidGeometry));
+        assert_eq!(Geometry::new(0, 9, 9, 1024), Err(VMError::InvalidGeometry));
+        assert_eq!(
+            Geometry::new(9, 9, 9, 40_000),
+            Err(VMError::InvalidGeometry)
+        );
+        assert_eq!(Geometry::new(4, 4, 17, 8), Err(VMError::InvalidGeometry));
+        assert_eq!(
+            Geometry::new(4, 12, 16, 32_768),
+            Err(VMError::InvalidGeometry)
+        );
+        assert!(Geometry::new(9, 9, 9, 32_768).is_ok());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    constants::{MAX_PAGE_OFFSET, MIN_POSITIVE_PAGE_OFFSET},
     error::{VMError, VMResult},
+    geometry::Geometry,
 };
 
 pub type Value = i16;
@@ -9,10 +9,13 @@ pub type Value = i16;
 pub struct FrameOffset(Value);
 
 impl FrameOffset {
-    pub fn new(input: &str) -> VMResult<Self> {
-        let input = input.parse().map_err(|_| VMError::InvalidFrame)?;
+    /// Parses a frame number, or a disk block when negative.
+    pub fn new(input: &str, geometry: &Geometry) -> VMResult<Self> {
+        let input: Value = input.parse().map_err(|_| VMError::InvalidFrame)?;
+        let min_frame = Value::try_from(geometry.min_frame())?;
+        let max_frame = Value::try_from(geometry.max_frame())?;
 
-        if (0..MIN_POSITIVE_PAGE_OFFSET).contains(&input) || input > MAX_PAGE_OFFSET {
+        if (0..min_frame).contains(&input) || input > max_frame || input < -max_frame {
             return Err(VMError::InvalidFrame);
         }
 
@@ -31,37 +34,46 @@ mod test {
 
     #[test]
     fn simple() {
-        let result = FrameOffset::new("2");
+        let result = FrameOffset::new("2", &Geometry::default());
         assert_eq!(result, Ok(FrameOffset(2)));
     }
 
     #[test]
     fn negative() {
-        let result = FrameOffset::new("-1");
+        let result = FrameOffset::new("-1", &Geometry::default());
         assert_eq!(result, Ok(FrameOffset(-1)));
     }
 
     #[test]
     fn zero() {
-        let result = FrameOffset::new("0");
+        let result = FrameOffset::new("0", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidFrame));
     }
 
     #[test]
     fn one() {
-        let result = FrameOffset::new("1");
+        let result = FrameOffset::new("1", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidFrame));
     }
 
     #[test]
     fn too_large() {
-        let result = FrameOffset::new("1024");
+        let result = FrameOffset::new("1024", &Geometry::default());
+        assert_eq!(result, Err(VMError::InvalidFrame));
+    }
+
+    #[test]
+    fn min_block() {
+        let result = FrameOffset::new("-1023", &Geometry::default());
+        assert_eq!(result, Ok(FrameOffset(-1023)));
+
+        let result = FrameOffset::new("-1024", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidFrame));
     }
 
     #[test]
     fn max() {
-        let result = FrameOffset::new("1023");
+        let result = FrameOffset::new("1023", &Geometry::default());
         assert_eq!(result, Ok(FrameOffset(1023)));
     }
 }
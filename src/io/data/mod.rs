@@ -1,4 +1,5 @@
 pub mod frame_offset;
 pub mod page_offset;
+pub mod protection;
 pub mod segment_offset;
 pub mod segment_size;
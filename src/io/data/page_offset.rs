@@ -1,6 +1,6 @@
 use crate::{
-    constants::MAX_PAGE_OFFSET,
     error::{VMError, VMResult},
+    geometry::Geometry,
 };
 
 pub type Value = u16;
@@ -9,10 +9,10 @@ pub type Value = u16;
 pub struct PageOffset(Value);
 
 impl PageOffset {
-    pub fn new(input: &str) -> VMResult<Self> {
-        let input = input.parse().map_err(|_| VMError::InvalidPage)?;
+    pub fn new(input: &str, geometry: &Geometry) -> VMResult<Self> {
+        let input: Value = input.parse().map_err(|_| VMError::InvalidPage)?;
 
-        if input > MAX_PAGE_OFFSET as Value {
+        if usize::from(input) > geometry.max_page() {
             return Err(VMError::InvalidPage);
         }
 
@@ -31,25 +31,25 @@ mod test {
 
     #[test]
     fn simple() {
-        let result = PageOffset::new("0");
+        let result = PageOffset::new("0", &Geometry::default());
         assert_eq!(result, Ok(PageOffset(0)));
     }
 
     #[test]
     fn negative() {
-        let result = PageOffset::new("-1");
+        let result = PageOffset::new("-1", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidPage));
     }
 
     #[test]
     fn too_large() {
-        let result = PageOffset::new("1024");
+        let result = PageOffset::new("512", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidPage));
     }
 
     #[test]
     fn max() {
-        let result = PageOffset::new("1023");
-        assert_eq!(result, Ok(PageOffset(1023)));
+        let result = PageOffset::new("511", &Geometry::default());
+        assert_eq!(result, Ok(PageOffset(511)));
     }
 }
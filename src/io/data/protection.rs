@@ -0,0 +1,81 @@
+use crate::{
+    error::{VMError, VMResult},
+    vm::protection::Permissions,
+};
+
+pub type Value = Permissions;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Protection(Value);
+
+impl Protection {
+    /// Parses an `rwx` style mode where each position is either its letter or `-`.
+    pub fn new(input: &str) -> VMResult<Self> {
+        let flags = [
+            ('r', Permissions::READ),
+            ('w', Permissions::WRITE),
+            ('x', Permissions::EXECUTE),
+        ];
+
+        if input.chars().count() != flags.len() {
+            return Err(VMError::InvalidProtection);
+        }
+
+        input
+            .chars()
+            .zip(flags)
+            .try_fold(Permissions::NONE, |permissions, (c, (flag, value))| {
+                if c == flag {
+                    Ok(permissions.union(value))
+                } else if c == '-' {
+                    Ok(permissions)
+                } else {
+                    Err(VMError::InvalidProtection)
+                }
+            })
+            .map(Self)
+    }
+
+    #[must_use]
+    pub const fn value(&self) -> Value {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn all() {
+        let result = Protection::new("rwx");
+        assert_eq!(result, Ok(Protection(Permissions::ALL)));
+    }
+
+    #[test]
+    fn none() {
+        let result = Protection::new("---");
+        assert_eq!(result, Ok(Protection(Permissions::NONE)));
+    }
+
+    #[test]
+    fn read_execute() {
+        let result = Protection::new("r-x");
+        assert_eq!(
+            result,
+            Ok(Protection(Permissions::READ.union(Permissions::EXECUTE)))
+        );
+    }
+
+    #[test]
+    fn out_of_order() {
+        let result = Protection::new("xwr");
+        assert_eq!(result, Err(VMError::InvalidProtection));
+    }
+
+    #[test]
+    fn too_short() {
+        let result = Protection::new("rw");
+        assert_eq!(result, Err(VMError::InvalidProtection));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    constants::MAX_SEGMENT_OFFSET,
     error::{VMError, VMResult},
+    geometry::Geometry,
 };
 
 pub type Value = u16;
@@ -9,10 +9,10 @@ pub type Value = u16;
 pub struct SegmentOffset(Value);
 
 impl SegmentOffset {
-    pub fn new(input: &str) -> VMResult<Self> {
-        let input = input.parse().map_err(|_| VMError::InvalidSegment)?;
+    pub fn new(input: &str, geometry: &Geometry) -> VMResult<Self> {
+        let input: Value = input.parse().map_err(|_| VMError::InvalidSegment)?;
 
-        if input > MAX_SEGMENT_OFFSET {
+        if usize::from(input) > geometry.max_segment() {
             return Err(VMError::InvalidSegment);
         }
 
@@ -31,25 +31,25 @@ mod test {
 
     #[test]
     fn simple() {
-        let result = SegmentOffset::new("0");
+        let result = SegmentOffset::new("0", &Geometry::default());
         assert_eq!(result, Ok(SegmentOffset(0)));
     }
 
     #[test]
     fn negative() {
-        let result = SegmentOffset::new("-1");
+        let result = SegmentOffset::new("-1", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidSegment));
     }
 
     #[test]
     fn too_large() {
-        let result = SegmentOffset::new("512");
+        let result = SegmentOffset::new("512", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidSegment));
     }
 
     #[test]
     fn max() {
-        let result = SegmentOffset::new("511");
+        let result = SegmentOffset::new("511", &Geometry::default());
         assert_eq!(result, Ok(SegmentOffset(511)));
     }
 }
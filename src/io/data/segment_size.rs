@@ -1,6 +1,6 @@
 use crate::{
-    constants::MAX_SEGMENT_SIZE,
     error::{VMError, VMResult},
+    geometry::Geometry,
 };
 
 pub type Value = u32;
@@ -9,10 +9,10 @@ pub type Value = u32;
 pub struct SegmentSize(Value);
 
 impl SegmentSize {
-    pub fn new(input: &str) -> VMResult<Self> {
-        let input = input.parse().map_err(|_| VMError::InvalidSegmentSize)?;
+    pub fn new(input: &str, geometry: &Geometry) -> VMResult<Self> {
+        let input: Value = input.parse().map_err(|_| VMError::InvalidSegmentSize)?;
 
-        if input > MAX_SEGMENT_SIZE {
+        if usize::try_from(input)? > geometry.max_segment_size() {
             return Err(VMError::InvalidSegmentSize);
         }
 
@@ -31,25 +31,25 @@ mod test {
 
     #[test]
     fn simple() {
-        let result = SegmentSize::new("0");
+        let result = SegmentSize::new("0", &Geometry::default());
         assert_eq!(result, Ok(SegmentSize(0)));
     }
 
     #[test]
     fn negative() {
-        let result = SegmentSize::new("-1");
+        let result = SegmentSize::new("-1", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidSegmentSize));
     }
 
     #[test]
     fn too_large() {
-        let result = SegmentSize::new("523265");
+        let result = SegmentSize::new("523265", &Geometry::default());
         assert_eq!(result, Err(VMError::InvalidSegmentSize));
     }
 
     #[test]
     fn max() {
-        let result = SegmentSize::new("523264");
+        let result = SegmentSize::new("523264", &Geometry::default());
         assert_eq!(result, Ok(SegmentSize(523_264)));
     }
 }
@@ -1,3 +1,5 @@
 pub mod data;
+pub mod protection_input;
 pub mod pt_input;
 pub mod st_input;
+pub mod validate;
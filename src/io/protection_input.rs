@@ -0,0 +1,39 @@
+use crate::{error::VMResult, geometry::Geometry};
+
+use super::data::{
+    page_offset::{self, PageOffset},
+    protection::{self, Protection},
+    segment_offset::{self, SegmentOffset},
+};
+
+/// Wildcard page that applies a protection to the whole segment.
+pub const WHOLE_SEGMENT: &str = "*";
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ProtectionInput {
+    pub segment: segment_offset::Value,
+    /// `None` when the protection applies to the segment itself.
+    pub page: Option<page_offset::Value>,
+    pub protection: protection::Value,
+}
+
+impl ProtectionInput {
+    /// Creates a new `ProtectionInput`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the segment, page, or protection are invalid.
+    pub fn new(segment: &str, page: &str, protection: &str, geometry: &Geometry) -> VMResult<Self> {
+        let page = if page == WHOLE_SEGMENT {
+            None
+        } else {
+            Some(PageOffset::new(page, geometry)?.value())
+        };
+
+        Ok(Self {
+            segment: SegmentOffset::new(segment, geometry)?.value(),
+            page,
+            protection: Protection::new(protection)?.value(),
+        })
+    }
+}
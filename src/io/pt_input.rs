@@ -1,11 +1,12 @@
 use crate::{
     error::VMResult,
+    geometry::Geometry,
     io::data::{frame_offset::FrameOffset, page_offset::PageOffset, segment_offset::SegmentOffset},
 };
 
 use super::data::{frame_offset, page_offset, segment_offset};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct PTInput {
     pub segment: segment_offset::Value,
     pub page: page_offset::Value,
@@ -18,11 +19,18 @@ impl PTInput {
     /// # Errors
     ///
     /// Returns an error if the segment, page, or frame are invalid.
-    pub fn new(segment: &str, page: &str, frame: &str) -> VMResult<Self> {
+    pub fn new(segment: &str, page: &str, frame: &str, geometry: &Geometry) -> VMResult<Self> {
         Ok(Self {
-            segment: SegmentOffset::new(segment)?.value(),
-            page: PageOffset::new(page)?.value(),
-            frame: FrameOffset::new(frame)?.value(),
+            segment: SegmentOffset::new(segment, geometry)?.value(),
+            page: PageOffset::new(page, geometry)?.value(),
+            frame: FrameOffset::new(frame, geometry)?.value(),
         })
     }
 }
+
+/// Formats the triple as it is written in an init file.
+impl std::fmt::Display for PTInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.segment, self.page, self.frame)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::error::VMResult;
+use crate::{error::VMResult, geometry::Geometry};
 
 use super::data::{
     frame_offset::{self, FrameOffset},
@@ -6,7 +6,7 @@ use super::data::{
     segment_size::{self, SegmentSize},
 };
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct STInput {
     pub segment: segment_offset::Value,
     pub size: segment_size::Value,
@@ -19,11 +19,18 @@ impl STInput {
     /// # Errors
     ///
     /// Returns an error if the segment, segment size, or frame are invalid.
-    pub fn new(segment: &str, size: &str, frame: &str) -> VMResult<Self> {
+    pub fn new(segment: &str, size: &str, frame: &str, geometry: &Geometry) -> VMResult<Self> {
         Ok(Self {
-            segment: SegmentOffset::new(segment)?.value(),
-            size: SegmentSize::new(size)?.value(),
-            frame: FrameOffset::new(frame)?.value(),
+            segment: SegmentOffset::new(segment, geometry)?.value(),
+            size: SegmentSize::new(size, geometry)?.value(),
+            frame: FrameOffset::new(frame, geometry)?.value(),
         })
     }
 }
+
+/// Formats the triple as it is written in an init file.
+impl std::fmt::Display for STInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.segment, self.size, self.frame)
+    }
+}
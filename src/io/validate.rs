@@ -0,0 +1,385 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    geometry::Geometry,
+    io::{pt_input::PTInput, st_input::STInput},
+};
+
+/// A conflict in an init file that `VirtualMemory` would silently accept.
+///
+/// `process` is the index of the process section the offending triple
+/// appears in, 0 for an init file without sections.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Diagnostic {
+    /// Two segments put their page tables in the same frame or disk block.
+    SharedPageTable {
+        process: usize,
+        segment: STInput,
+        other_process: usize,
+        other: STInput,
+    },
+    /// A page uses the frame or disk block of a page table.
+    PageTableCollision {
+        process: usize,
+        page: PTInput,
+        page_table_process: usize,
+        page_table: STInput,
+    },
+    /// Two pages use the same frame or disk block.
+    SharedPage {
+        process: usize,
+        page: PTInput,
+        other_process: usize,
+        other: PTInput,
+    },
+    /// A page table entry for a segment with no segment table entry.
+    UndeclaredSegment { process: usize, page: PTInput },
+    /// A segment declared more than once in the same process.
+    DuplicateSegment {
+        process: usize,
+        segment: STInput,
+        other: STInput,
+    },
+    /// A segment larger than its page table can map.
+    SegmentTooLarge {
+        process: usize,
+        segment: STInput,
+        max_size: usize,
+    },
+}
+
+/// Frame or disk block named by the frame of an init triple.
+fn location(frame: i16) -> String {
+    if frame < 0 {
+        format!("block {}", frame.unsigned_abs())
+    } else {
+        format!("frame {frame}")
+    }
+}
+
+/// Names the process of the other triple only when it differs.
+fn other_process(process: usize, other_process: usize) -> String {
+    if process == other_process {
+        String::new()
+    } else {
+        format!("process {other_process} ")
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::SharedPageTable {
+                process,
+                segment,
+                other_process: other,
+                other: other_segment,
+            } => write!(
+                f,
+                "process {process}: segment table entry ({segment}) shares page table {} with {}({other_segment})",
+                location(segment.frame),
+                other_process(process, other)
+            ),
+            Self::PageTableCollision {
+                process,
+                page,
+                page_table_process,
+                page_table,
+            } => write!(
+                f,
+                "process {process}: page table entry ({page}) puts a page in page table {} of {}({page_table})",
+                location(page.frame),
+                other_process(process, page_table_process)
+            ),
+            Self::SharedPage {
+                process,
+                page,
+                other_process: other,
+                other: other_page,
+            } => write!(
+                f,
+                "process {process}: page table entry ({page}) shares {} with {}({other_page})",
+                location(page.frame),
+                other_process(process, other)
+            ),
+            Self::UndeclaredSegment { process, page } => write!(
+                f,
+                "process {process}: page table entry ({page}) names undeclared segment {}",
+                page.segment
+            ),
+            Self::DuplicateSegment {
+                process,
+                segment,
+                other,
+            } => write!(
+                f,
+                "process {process}: segment table entry ({segment}) redeclares segment {} from ({other})",
+                segment.segment
+            ),
+            Self::SegmentTooLarge {
+                process,
+                segment,
+                max_size,
+            } => write!(
+                f,
+                "process {process}: segment table entry ({segment}) is larger than the {max_size} words its page table can map"
+            ),
+        }
+    }
+}
+
+/// What an init triple stores in a frame or disk block.
+#[derive(Clone, Copy)]
+enum Claim {
+    PageTable(usize, STInput),
+    Page(usize, PTInput),
+}
+
+/// Checks the segment and page table inputs of every process against each
+/// other, returning every conflict found in input order.
+///
+/// Frames and disk blocks are shared by all processes, so a collision
+/// between two processes is reported like one within a process.
+#[must_use]
+pub fn validate(
+    processes: &[(Vec<STInput>, Vec<PTInput>)],
+    geometry: &Geometry,
+) -> Vec<Diagnostic> {
+    let max_size = geometry.pages_per_segment() * geometry.page_size();
+    let mut claims: HashMap<i16, Claim> = HashMap::new();
+    let mut diagnostics = Vec::new();
+
+    for (process, (st_inputs, pt_inputs)) in processes.iter().enumerate() {
+        let mut segments: HashMap<u16, STInput> = HashMap::new();
+
+        for &segment in st_inputs {
+            if let Some(&other) = segments.get(&segment.segment) {
+                diagnostics.push(Diagnostic::DuplicateSegment {
+                    process,
+                    segment,
+                    other,
+                });
+            }
+            segments.insert(segment.segment, segment);
+
+            if usize::try_from(segment.size).unwrap_or(usize::MAX) > max_size {
+                diagnostics.push(Diagnostic::SegmentTooLarge {
+                    process,
+                    segment,
+                    max_size,
+                });
+            }
+
+            match claims.get(&segment.frame) {
+                Some(&Claim::PageTable(other_process, other)) => {
+                    diagnostics.push(Diagnostic::SharedPageTable {
+                        process,
+                        segment,
+                        other_process,
+                        other,
+                    });
+                }
+                Some(&Claim::Page(page_process, page)) => {
+                    diagnostics.push(Diagnostic::PageTableCollision {
+                        process: page_process,
+                        page,
+                        page_table_process: process,
+                        page_table: segment,
+                    });
+                }
+                None => {
+                    claims.insert(segment.frame, Claim::PageTable(process, segment));
+                }
+            }
+        }
+
+        let declared: HashSet<u16> = segments.into_keys().collect();
+
+        for &page in pt_inputs {
+            if !declared.contains(&page.segment) {
+                diagnostics.push(Diagnostic::UndeclaredSegment { process, page });
+                continue;
+            }
+
+            match claims.get(&page.frame) {
+                Some(&Claim::PageTable(page_table_process, page_table)) => {
+                    diagnostics.push(Diagnostic::PageTableCollision {
+                        process,
+                        page,
+                        page_table_process,
+                        page_table,
+                    });
+                }
+                Some(&Claim::Page(other_process, other)) => {
+                    diagnostics.push(Diagnostic::SharedPage {
+                        process,
+                        page,
+                        other_process,
+                        other,
+                    });
+                }
+                None => {
+                    claims.insert(page.frame, Claim::Page(process, page));
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Checks the page table inputs of every process for pages sharing a frame
+/// or disk block, for the schemes that build their tables from the pages
+/// alone. The segment table inputs are ignored.
+#[must_use]
+pub fn validate_pages(processes: &[(Vec<STInput>, Vec<PTInput>)]) -> Vec<Diagnostic> {
+    let mut claims: HashMap<i16, (usize, PTInput)> = HashMap::new();
+    let mut diagnostics = Vec::new();
+
+    for (process, (_, pt_inputs)) in processes.iter().enumerate() {
+        for &page in pt_inputs {
+            match claims.get(&page.frame) {
+                Some(&(other_process, other)) => {
+                    diagnostics.push(Diagnostic::SharedPage {
+                        process,
+                        page,
+                        other_process,
+                        other,
+                    });
+                }
+                None => {
+                    claims.insert(page.frame, (process, page));
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn st(segment: &str, size: &str, frame: &str) -> STInput {
+        STInput::new(segment, size, frame, &Geometry::default()).expect("Failed to create STInput")
+    }
+
+    fn pt(segment: &str, page: &str, frame: &str) -> PTInput {
+        PTInput::new(segment, page, frame, &Geometry::default()).expect("Failed to create PTInput")
+    }
+
+    #[test]
+    fn consistent() {
+        let processes = vec![(
+            vec![st("8", "4000", "3"), st("9", "5000", "-7")],
+            vec![pt("8", "0", "10"), pt("8", "1", "-20"), pt("9", "0", "13")],
+        )];
+
+        assert_eq!(validate(&processes, &Geometry::default()), vec![]);
+    }
+
+    #[test]
+    fn conflicts() {
+        let processes = vec![(
+            vec![
+                st("8", "4000", "3"),
+                st("9", "5000", "3"),
+                st("8", "100", "5"),
+                st("10", "300000", "6"),
+            ],
+            vec![
+                pt("8", "0", "5"),
+                pt("7", "0", "11"),
+                pt("9", "0", "12"),
+                pt("9", "1", "12"),
+            ],
+        )];
+
+        assert_eq!(
+            validate(&processes, &Geometry::default()),
+            vec![
+                Diagnostic::SharedPageTable {
+                    process: 0,
+                    segment: st("9", "5000", "3"),
+                    other_process: 0,
+                    other: st("8", "4000", "3"),
+                },
+                Diagnostic::DuplicateSegment {
+                    process: 0,
+                    segment: st("8", "100", "5"),
+                    other: st("8", "4000", "3"),
+                },
+                Diagnostic::SegmentTooLarge {
+                    process: 0,
+                    segment: st("10", "300000", "6"),
+                    max_size: 262_144,
+                },
+                Diagnostic::PageTableCollision {
+                    process: 0,
+                    page: pt("8", "0", "5"),
+                    page_table_process: 0,
+                    page_table: st("8", "100", "5"),
+                },
+                Diagnostic::UndeclaredSegment {
+                    process: 0,
+                    page: pt("7", "0", "11"),
+                },
+                Diagnostic::SharedPage {
+                    process: 0,
+                    page: pt("9", "1", "12"),
+                    other_process: 0,
+                    other: pt("9", "0", "12"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn across_processes() {
+        let processes = vec![
+            (vec![st("8", "4000", "3")], vec![pt("8", "0", "10")]),
+            (vec![st("8", "4000", "10")], vec![pt("8", "0", "-20")]),
+        ];
+
+        let diagnostics = validate(&processes, &Geometry::default());
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic::PageTableCollision {
+                process: 0,
+                page: pt("8", "0", "10"),
+                page_table_process: 1,
+                page_table: st("8", "4000", "10"),
+            }]
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "process 0: page table entry (8 0 10) puts a page in page table frame 10 of process 1 (8 4000 10)"
+        );
+    }
+
+    #[test]
+    fn pages_only() {
+        let processes = vec![
+            (
+                vec![st("6", "3000", "5")],
+                vec![pt("6", "0", "7"), pt("6", "1", "-9")],
+            ),
+            (vec![], vec![pt("6", "0", "5"), pt("6", "2", "-9")]),
+        ];
+
+        // The page in frame 5 is fine without the segment table entry
+        assert_eq!(
+            validate_pages(&processes),
+            vec![Diagnostic::SharedPage {
+                process: 1,
+                page: pt("6", "2", "-9"),
+                other_process: 0,
+                other: pt("6", "1", "-9"),
+            }]
+        );
+    }
+}
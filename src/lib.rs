@@ -1,5 +1,5 @@
-#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
 pub mod constants;
 pub mod error;
+pub mod geometry;
 pub mod io;
 pub mod vm;
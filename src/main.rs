@@ -1,14 +1,88 @@
+mod cli;
 mod process;
+mod repl;
 
-use process::process;
+use std::{
+    io::{stdin, stdout, IsTerminal},
+    process::ExitCode,
+};
 
-fn main() {
-    match process(
-        "init-dp.txt".into(),
-        "input-dp.txt".into(),
-        "output-dp.txt".into(),
-    ) {
-        Ok(()) => println!("Process completed successfully"),
-        Err(e) => println!("Process failed: {:?}", e),
+use virtual_memory::error::display_chain;
+
+use cli::{Args, Command, USAGE};
+use process::{load, process, summary, validate_init};
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    if args.command == Command::Repl {
+        let mut options = args.options;
+        let result = load(&args.init, &mut options).and_then(|mut virtual_memory| {
+            repl::run(
+                &mut virtual_memory,
+                stdin().lock(),
+                &mut stdout(),
+                stdin().is_terminal(),
+            )
+        });
+
+        return match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("Repl failed: {}", display_chain(&e));
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if args.command == Command::Validate {
+        return match validate_init(&args.init, &args.options.geometry) {
+            Ok(diagnostics) if diagnostics.is_empty() => {
+                eprintln!("No conflicts found");
+                ExitCode::SUCCESS
+            }
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics {
+                    println!("{diagnostic}");
+                }
+                ExitCode::FAILURE
+            }
+            Err(e) => {
+                eprintln!("Validation failed: {}", display_chain(&e));
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let stats_written = args.options.stats.is_some();
+    match process(&args.init, &args.input, &args.output, args.options) {
+        Ok(virtual_memory) => {
+            eprintln!("Process completed successfully");
+
+            // TLB and inverted table counters are worth showing even without
+            // --stats, in the same words --stats uses
+            let has_tables =
+                virtual_memory.tlb_stats().is_some() || virtual_memory.inverted_stats().is_some();
+            if !stats_written && has_tables {
+                eprintln!("{}", summary(&virtual_memory));
+            }
+
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Process failed: {}", display_chain(&e));
+            ExitCode::FAILURE
+        }
     }
 }
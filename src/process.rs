@@ -1,96 +1,532 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Read, Write},
-    path::PathBuf,
+    io::{stdin, stdout, Read, Write},
+    path::{Path, PathBuf},
 };
 
 use virtual_memory::{
     error::{VMError, VMResult},
-    io::{pt_input::PTInput, st_input::STInput},
-    vm::{virtual_address::VirtualAddress, virtual_memory::VirtualMemory},
+    geometry::Geometry,
+    io::{
+        protection_input::ProtectionInput,
+        pt_input::PTInput,
+        st_input::STInput,
+        validate::{validate, validate_pages, Diagnostic},
+    },
+    vm::{
+        allocator::Fit,
+        frame::FrameKind,
+        replacement::ReplacementPolicy,
+        scheme::Scheme,
+        tlb::TlbConfig,
+        trace::Trace,
+        virtual_address::VirtualAddress,
+        virtual_memory::{Address, ProcessId, VirtualMemory},
+    },
 };
 
-fn process_init(file_path: PathBuf) -> VMResult<VirtualMemory> {
-    let f = File::open(file_path)?;
-    let mut reader = BufReader::new(f);
+/// Path standing for standard input or standard output.
+pub const STDIO_PATH: &str = "-";
 
-    let mut line = String::new();
-    reader.read_line(&mut line)?;
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum OutputFormat {
+    /// All results on one line, separated by spaces.
+    #[default]
+    Text,
+    /// One result per line.
+    Lines,
+    /// A JSON array with one record per address.
+    Json,
+    /// One JSON record per line.
+    JsonLines,
+}
+
+impl OutputFormat {
+    const fn is_json(self) -> bool {
+        matches!(self, Self::Json | Self::JsonLines)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub geometry: Geometry,
+    pub policy: Option<Box<dyn ReplacementPolicy>>,
+    pub tlb: Option<TlbConfig>,
+    /// How addresses are translated. Snapshots bring their own.
+    pub scheme: Scheme,
+    /// How pure segmentation picks the hole a segment is loaded into.
+    pub fit: Option<Fit>,
+    /// Map zeroed frames for 0 entries inside a segment instead of failing.
+    pub demand_zero: bool,
+    /// Page segment tables behind a resident directory instead of pinning them.
+    pub paged_segment_table: bool,
+    pub format: OutputFormat,
+    /// Where to write a summary of the run's statistics, if anywhere.
+    pub stats: Option<PathBuf>,
+    /// Where to write a line per input address explaining its translation, if anywhere.
+    pub trace: Option<PathBuf>,
+    /// Snapshot to start from instead of the init file, if any.
+    pub restore: Option<PathBuf>,
+    /// Where to save a snapshot of the final state, if anywhere.
+    pub snapshot: Option<PathBuf>,
+}
+
+fn read_input(path: &Path) -> VMResult<String> {
+    let mut data = String::new();
+
+    if path == Path::new(STDIO_PATH) {
+        stdin().read_to_string(&mut data)?;
+    } else {
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut data))
+            .map_err(|error| VMError::from(error).in_file(path))?;
+    }
+
+    Ok(data)
+}
+
+fn create_output(path: &Path) -> VMResult<Box<dyn Write>> {
+    if path == Path::new(STDIO_PATH) {
+        Ok(Box::new(stdout()))
+    } else {
+        Ok(Box::new(
+            File::create(path).map_err(|error| VMError::from(error).in_file(path))?,
+        ))
+    }
+}
+
+/// Creates `path` and fills it with `write`, naming the file in any error.
+fn write_output(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+) -> VMResult<()> {
+    let mut file = create_output(path)?;
+    write(&mut file).map_err(|error| VMError::from(error).in_file(path))
+}
 
-    let st_inputs: Vec<STInput> = line
-        .split_whitespace()
-        .collect::<Vec<&str>>()
+/// Header line that starts a process section in a multi-process init file.
+const PROCESS_SECTION: &str = "process";
+
+/// Separates the process id from the address in a `pid:address` input token.
+const PROCESS_SEPARATOR: char = ':';
+
+/// Wraps `error` with the position of the offending token.
+fn parse_error(file: &Path, line: usize, token: usize, error: VMError) -> VMError {
+    VMError::Parse {
+        file: file.to_path_buf(),
+        line,
+        token,
+        error: Box::new(error),
+    }
+}
+
+/// Position of the field an init triple constructor rejected within its triple.
+const fn field_offset(error: &VMError) -> usize {
+    match error {
+        VMError::InvalidSegment => 0,
+        VMError::InvalidSegmentSize | VMError::InvalidPage => 1,
+        _ => 2,
+    }
+}
+
+/// Parses `line` as a sequence of triples with `new`.
+///
+/// `line_number` is 1-based and only used to locate errors.
+fn parse_triples<T>(
+    file: &Path,
+    line_number: usize,
+    line: &str,
+    new: impl Fn(&str, &str, &str) -> VMResult<T>,
+) -> VMResult<Vec<T>> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+
+    tokens
         .chunks(3)
-        .map(|chunk| {
-            STInput::new(chunk[0], chunk[1], chunk[2]).expect("Invalid st_input in init file")
+        .enumerate()
+        .map(|(i, chunk)| {
+            let token = i * 3 + 1;
+            match *chunk {
+                [first, second, third] => new(first, second, third).map_err(|error| {
+                    let token = token + field_offset(&error);
+                    parse_error(file, line_number, token, error)
+                }),
+                _ => Err(parse_error(
+                    file,
+                    line_number,
+                    token,
+                    VMError::IncompleteTriple,
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Parses the segment table, page table and optional protection lines of one process.
+///
+/// `first_line` is the 1-based line number of the section's first line.
+fn parse_section(
+    file: &Path,
+    first_line: usize,
+    section: &[&str],
+    geometry: &Geometry,
+) -> VMResult<(Vec<STInput>, Vec<PTInput>, Vec<ProtectionInput>)> {
+    let line = |i: usize| section.get(i).copied().unwrap_or_default();
+
+    let st_inputs = parse_triples(file, first_line, line(0), |segment, size, frame| {
+        STInput::new(segment, size, frame, geometry)
+    })?;
+
+    let pt_inputs = parse_triples(file, first_line + 1, line(1), |segment, page, frame| {
+        PTInput::new(segment, page, frame, geometry)
+    })?;
+
+    // Optional third line of protections
+    let protection_inputs = parse_triples(file, first_line + 2, line(2), |segment, page, mode| {
+        ProtectionInput::new(segment, page, mode, geometry)
+    })?;
+
+    Ok((st_inputs, pt_inputs, protection_inputs))
+}
+
+/// Splits the lines of an init file into one section per process, each with
+/// the 1-based line number it starts at.
+fn sections<'a>(lines: &'a [&'a str]) -> Vec<(usize, &'a [&'a str])> {
+    // Without section headers the whole file describes a single process
+    if lines.first().map(|line| line.trim()) != Some(PROCESS_SECTION) {
+        return vec![(1, lines)];
+    }
+
+    let headers: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == PROCESS_SECTION)
+        .map(|(i, _)| i)
+        .collect();
+
+    headers
+        .iter()
+        .enumerate()
+        .map(|(i, &header)| {
+            let end = headers.get(i + 1).copied().unwrap_or(lines.len());
+            (header + 2, &lines[header + 1..end])
         })
+        .collect()
+}
+
+/// Checks an init file for conflicts between its triples.
+pub fn validate_init(file_path: &Path, geometry: &Geometry) -> VMResult<Vec<Diagnostic>> {
+    let init_data = read_input(file_path)?;
+    let lines: Vec<&str> = init_data.lines().collect();
+
+    let processes = sections(&lines)
+        .into_iter()
+        .map(|(first_line, section)| {
+            let (st_inputs, pt_inputs, _) =
+                parse_section(file_path, first_line, section, geometry)?;
+            Ok((st_inputs, pt_inputs))
+        })
+        .collect::<VMResult<Vec<_>>>()?;
+
+    Ok(validate(&processes, geometry))
+}
+
+/// Builds a `VirtualMemory` from an init file, with one process per section.
+///
+/// # Errors
+/// - `VMError::InitConflict` for the first conflict `validate` finds with
+///   the segmented scheme, or `validate_pages` with the schemes building
+///   their tables from the pages alone.
+/// - Any error loading the tables, in the context of the file.
+fn process_init(file_path: &Path, geometry: Geometry, scheme: Scheme) -> VMResult<VirtualMemory> {
+    let init_data = read_input(file_path)?;
+    let lines: Vec<&str> = init_data.lines().collect();
+
+    let sections = sections(&lines)
+        .into_iter()
+        .map(|(first_line, section)| parse_section(file_path, first_line, section, &geometry))
+        .collect::<VMResult<Vec<_>>>()?;
+
+    let processes: Vec<_> = sections
+        .iter()
+        .map(|(st_inputs, pt_inputs, _)| (st_inputs.clone(), pt_inputs.clone()))
         .collect();
+    let diagnostics = match scheme {
+        Scheme::Segmented => validate(&processes, &geometry),
+        Scheme::Radix { .. } | Scheme::Inverted | Scheme::PurePaging => validate_pages(&processes),
+        // Overlapping segments are rejected while they are loaded
+        Scheme::PureSegmentation => Vec::new(),
+    };
+    if let Some(&diagnostic) = diagnostics.first() {
+        return Err(VMError::InitConflict(diagnostic).in_file(file_path));
+    }
 
-    line.clear();
-    reader.read_line(&mut line)?;
+    let mut virtual_memory: Option<VirtualMemory> = None;
+    let in_file = |error: VMError| error.in_file(file_path);
 
-    let pt_inputs: Vec<PTInput> = line
-        .split_whitespace()
-        .collect::<Vec<&str>>()
-        .chunks(3)
-        .map(|chunk| {
-            PTInput::new(chunk[0], chunk[1], chunk[2]).expect("Invalid pt_input in init file")
+    for (st_inputs, pt_inputs, protection_inputs) in sections {
+        match virtual_memory.as_mut() {
+            None => {
+                virtual_memory = Some(
+                    VirtualMemory::with_scheme(geometry, scheme, st_inputs, pt_inputs)
+                        .map_err(in_file)?
+                        .with_protection(protection_inputs),
+                );
+            }
+            Some(virtual_memory) => {
+                let process = virtual_memory
+                    .create_process(st_inputs, pt_inputs)
+                    .map_err(in_file)?;
+                virtual_memory.switch_to(process)?;
+                virtual_memory.protect(protection_inputs);
+                virtual_memory.switch_to(0)?;
+            }
+        }
+    }
+
+    virtual_memory.map_or_else(
+        || VirtualMemory::with_scheme(geometry, scheme, vec![], vec![]),
+        Ok,
+    )
+}
+
+/// The fault counters, the TLB's hit ratio if there is one, the inverted
+/// page table's size and probe length if there is one and how free frames
+/// are split into holes with pure segmentation.
+pub fn summary(virtual_memory: &VirtualMemory) -> String {
+    let mut lines = vec![virtual_memory.stats().to_string()];
+
+    if let Some(tlb) = virtual_memory.tlb_stats() {
+        lines.push(format!(
+            "tlb: {} hits, {} misses, {:.2}% hit ratio",
+            tlb.hits,
+            tlb.misses,
+            tlb.hit_ratio() * 100.0
+        ));
+    }
+
+    if let Some(inverted) = virtual_memory.inverted_stats() {
+        lines.push(format!(
+            "inverted page table: {} frames, {} lookups, {:.2} average probe length",
+            inverted.table_frames,
+            inverted.lookups,
+            inverted.average_probe_length()
+        ));
+    }
+
+    if virtual_memory.scheme() == Scheme::PureSegmentation {
+        lines.push(virtual_memory.fragmentation().to_string());
+    }
+
+    lines.join("\n")
+}
+
+/// Formats one translation as a JSON object.
+///
+/// Every string written is a fixed identifier, so nothing needs escaping.
+fn json_record(virtual_address: Address, process: ProcessId, trace: &Trace) -> String {
+    let (physical_address, error) = match &trace.result {
+        Ok(physical_address) => (physical_address.to_string(), "null".to_string()),
+        Err(error) => ("null".to_string(), format!("\"{}\"", error.code())),
+    };
+
+    let page_ins: Vec<String> = trace
+        .page_ins
+        .iter()
+        .map(|page_in| {
+            let kind = match page_in.kind {
+                FrameKind::SegmentTable => "segment_table",
+                FrameKind::SegmentDirectory => "segment_directory",
+                FrameKind::PageDirectory => "page_directory",
+                FrameKind::InvertedTable => "inverted_table",
+                FrameKind::Segment => "segment",
+                FrameKind::PageTable => "page_table",
+                FrameKind::Page => "page",
+            };
+            format!(
+                "{{\"kind\":\"{kind}\",\"block\":{},\"frame\":{}}}",
+                page_in
+                    .block
+                    .map_or_else(|| "null".to_string(), |block| block.to_string()),
+                page_in.frame
+            )
         })
         .collect();
 
-    let virtual_memory =
-        VirtualMemory::new(st_inputs, pt_inputs).expect("Failed to initialize virtual memory");
+    format!(
+        "{{\"virtual_address\":{virtual_address},\"process\":{process},\
+         \"physical_address\":{physical_address},\"error\":{error},\"page_ins\":[{}]}}",
+        page_ins.join(",")
+    )
+}
+
+/// Builds the `VirtualMemory` from the init file or snapshot, applying the
+/// replacement policy and TLB taken out of `options`.
+pub fn load(init_file_path: &Path, options: &mut Options) -> VMResult<VirtualMemory> {
+    let mut virtual_memory = match &options.restore {
+        Some(snapshot_file_path) => {
+            VirtualMemory::load_snapshot(&mut read_input(snapshot_file_path)?.as_bytes())
+                .map_err(|error| error.in_file(snapshot_file_path))?
+        }
+        None => process_init(init_file_path, options.geometry, options.scheme)?,
+    };
+
+    if let Some(policy) = options.policy.take() {
+        virtual_memory = virtual_memory.with_policy(policy);
+    }
+
+    if let Some(tlb) = options.tlb.take() {
+        virtual_memory = virtual_memory.with_tlb(tlb)?;
+    }
+
+    if let Some(fit) = options.fit {
+        virtual_memory = virtual_memory.with_fit(fit);
+    }
+
+    if options.demand_zero {
+        virtual_memory = virtual_memory.with_demand_zero(true);
+    }
+
+    if options.paged_segment_table {
+        virtual_memory = virtual_memory.with_paged_segment_table()?;
+    }
 
     Ok(virtual_memory)
 }
 
+/// Parses an input token, either an address or `pid:address`.
+fn parse_address(
+    token: &str,
+    geometry: &Geometry,
+) -> VMResult<(Option<ProcessId>, Address, VirtualAddress)> {
+    let (process, address) = match token.split_once(PROCESS_SEPARATOR) {
+        Some((process, address)) => (
+            Some(process.parse().map_err(|_| VMError::InvalidProcess)?),
+            address,
+        ),
+        None => (None, token),
+    };
+    let address = address.parse().map_err(|_| VMError::InvalidAddress)?;
+
+    Ok((process, address, VirtualAddress::new(address, geometry)?))
+}
+
 pub fn process(
-    init_file_path: PathBuf,
-    input_file_path: PathBuf,
-    output_file_path: PathBuf,
-) -> VMResult<()> {
-    let mut virtual_memory = process_init(init_file_path)?;
+    init_file_path: &Path,
+    input_file_path: &Path,
+    output_file_path: &Path,
+    mut options: Options,
+) -> VMResult<VirtualMemory> {
+    let mut virtual_memory = load(init_file_path, &mut options)?;
+    // A snapshot brings its own geometry
+    let geometry = *virtual_memory.geometry();
 
-    let mut input_file = File::open(input_file_path)?;
+    let input_data = read_input(input_file_path)?;
 
-    let mut input_data = String::new();
-    input_file.read_to_string(&mut input_data)?;
+    let mut results = Vec::new();
+    let mut traces = Vec::new();
 
-    let output_data: String = input_data
-        .split_whitespace()
-        .map(|address| {
-            let virtual_address = VirtualAddress::new(address.parse().expect("Invalid Input Data"))
-                .expect("Invalid Data");
+    let tokens = input_data.lines().enumerate().flat_map(|(line, text)| {
+        text.split_whitespace()
+            .enumerate()
+            .map(move |(token, text)| (line + 1, token + 1, text))
+    });
 
-            match virtual_memory.translate(virtual_address) {
-                Ok(physical_address) => physical_address.to_string(),
-                Err(VMError::VirtualAddressOutOfBounds | VMError::MemoryNotInitialized) => {
-                    (-1).to_string()
-                }
-                Err(error) => panic!("{:?}", error),
+    for (line, token_index, token) in tokens {
+        let located = |error| parse_error(input_file_path, line, token_index, error);
+        let (process, address, virtual_address) =
+            parse_address(token, &geometry).map_err(located)?;
+
+        if let Some(process) = process {
+            virtual_memory.switch_to(process).map_err(located)?;
+        }
+
+        let (result, record) = if options.trace.is_some() || options.format.is_json() {
+            let trace = virtual_memory.translate_traced(virtual_address);
+            if options.trace.is_some() {
+                traces.push(format!("{token} {trace}"));
             }
-        })
-        .collect::<Vec<String>>()
-        .join(" ");
+            let record = options
+                .format
+                .is_json()
+                .then(|| json_record(address, virtual_memory.active_process(), &trace));
+            (trace.result, record)
+        } else {
+            (virtual_memory.translate(virtual_address), None)
+        };
 
-    let mut output_file = File::create(output_file_path)?;
-    writeln!(output_file, "{}", output_data)?;
+        // Faults of a single address are reported in place in every format,
+        // anything else like running out of frames or blocks aborts
+        let text = match result {
+            Ok(physical_address) => physical_address.to_string(),
+            Err(
+                VMError::VirtualAddressOutOfBounds
+                | VMError::MemoryNotInitialized
+                | VMError::ProtectionFault,
+            ) => (-1).to_string(),
+            Err(error) => {
+                return Err(VMError::Address {
+                    address,
+                    error: Box::new(error),
+                })
+            }
+        };
+        // JSON records carry the error code instead
+        results.push(record.unwrap_or(text));
+    }
+
+    let output = match options.format {
+        OutputFormat::Text => results.join(" "),
+        OutputFormat::Lines | OutputFormat::JsonLines => results.join("\n"),
+        OutputFormat::Json if results.is_empty() => "[]".to_string(),
+        OutputFormat::Json => format!("[\n  {}\n]", results.join(",\n  ")),
+    };
+
+    write_output(output_file_path, |file| writeln!(file, "{output}"))?;
+
+    if let Some(trace_file_path) = &options.trace {
+        write_output(trace_file_path, |file| {
+            traces
+                .iter()
+                .try_for_each(|trace| writeln!(file, "{trace}"))
+        })?;
+    }
 
-    Ok(())
+    if let Some(stats_file_path) = &options.stats {
+        write_output(stats_file_path, |file| {
+            writeln!(file, "{}", summary(&virtual_memory))
+        })?;
+    }
+
+    if let Some(snapshot_file_path) = &options.snapshot {
+        virtual_memory
+            .save_snapshot(&mut create_output(snapshot_file_path)?)
+            .map_err(|error| error.in_file(snapshot_file_path))?;
+    }
+
+    Ok(virtual_memory)
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs::read_to_string;
 
+    use virtual_memory::{
+        error::display_chain,
+        vm::{
+            replacement::Lru,
+            tlb::{TlbReplacement, TlbStats},
+        },
+    };
+
     use super::*;
 
     #[test]
     fn basic() {
         process(
-            "test-data/init.txt".into(),
-            "test-data/input.txt".into(),
-            "test-data/output.tmp".into(),
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/input.txt"),
+            Path::new("test-data/output.tmp"),
+            Options::default(),
         )
         .expect("Failed to process basic example");
 
@@ -104,9 +540,10 @@ mod tests {
     #[test]
     fn error() {
         process(
-            "test-data/error_init.txt".into(),
-            "test-data/error_input.txt".into(),
-            "test-data/error_output.tmp".into(),
+            Path::new("test-data/error_init.txt"),
+            Path::new("test-data/error_input.txt"),
+            Path::new("test-data/error_output.tmp"),
+            Options::default(),
         )
         .expect("Failed to process error example");
 
@@ -116,4 +553,671 @@ mod tests {
 
         assert_eq!(expected_output, output);
     }
+
+    #[test]
+    fn address_error() {
+        let options = |format| Options {
+            scheme: Scheme::PureSegmentation,
+            format,
+            ..Options::default()
+        };
+
+        // The second segment is one frame longer than the hole the first leaves
+        // Running out of frames aborts whatever the output format
+        for format in [
+            OutputFormat::Text,
+            OutputFormat::Json,
+            OutputFormat::JsonLines,
+        ] {
+            let error = process(
+                Path::new("test-data/memory_full_init.txt"),
+                Path::new("test-data/memory_full_input.txt"),
+                Path::new("test-data/memory_full_output.tmp"),
+                options(format),
+            )
+            .map(|_| ())
+            .expect_err("Translation past the end of memory succeeded");
+            assert_eq!(
+                error,
+                VMError::Address {
+                    address: 2_359_296,
+                    error: Box::new(VMError::MemoryFull),
+                }
+            );
+            assert_eq!(
+                display_chain(&error),
+                "address 2359296: physical memory is full"
+            );
+        }
+    }
+
+    #[test]
+    fn demand_zero() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/trace_input.txt"),
+            Path::new("test-data/demand_zero_output.tmp"),
+            Options {
+                demand_zero: true,
+                stats: Some(PathBuf::from("test-data/demand_zero_summary.tmp")),
+                trace: Some(PathBuf::from("test-data/demand_zero_trace.tmp")),
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process demand zero example");
+
+        // The uninitialized page of segment 8 is zeroed instead of giving -1
+        let output =
+            read_to_string("test-data/demand_zero_output.tmp").expect("Failed to read output");
+        assert_eq!(output, "5130 2058 -1 2560 6666\n");
+
+        for name in ["summary", "trace"] {
+            let expected = read_to_string(format!("test-data/demand_zero_{name}.txt"))
+                .expect("Failed to read expected file");
+            let actual = read_to_string(format!("test-data/demand_zero_{name}.tmp"))
+                .expect("Failed to read file");
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn multiple_processes() {
+        process(
+            Path::new("test-data/multi_init.txt"),
+            Path::new("test-data/multi_input.txt"),
+            Path::new("test-data/multi_output.tmp"),
+            Options::default(),
+        )
+        .expect("Failed to process multi-process example");
+
+        let expected_output =
+            read_to_string("test-data/multi_output.txt").expect("Failed to read expected output");
+        let output = read_to_string("test-data/multi_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn paged_segment_table() {
+        // Paging the segment tables only changes where they are kept, not
+        // what the addresses translate to
+        process(
+            Path::new("test-data/multi_init.txt"),
+            Path::new("test-data/multi_input.txt"),
+            Path::new("test-data/paged_output.tmp"),
+            Options {
+                paged_segment_table: true,
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process paged segment table example");
+
+        let expected_output =
+            read_to_string("test-data/multi_output.txt").expect("Failed to read expected output");
+        let output = read_to_string("test-data/paged_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn radix() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/input.txt"),
+            Path::new("test-data/radix_output.tmp"),
+            Options {
+                scheme: Scheme::Radix { levels: 2 },
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process radix example");
+
+        let expected_output =
+            read_to_string("test-data/radix_output.txt").expect("Failed to read expected output");
+        let output = read_to_string("test-data/radix_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn inverted() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/input.txt"),
+            Path::new("test-data/inverted_output.tmp"),
+            Options {
+                scheme: Scheme::Inverted,
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process inverted example");
+
+        let expected_output = read_to_string("test-data/inverted_output.txt")
+            .expect("Failed to read expected output");
+        let output =
+            read_to_string("test-data/inverted_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn pure_segmentation() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/input.txt"),
+            Path::new("test-data/pure_segmentation_output.tmp"),
+            Options {
+                scheme: Scheme::PureSegmentation,
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process pure segmentation example");
+
+        let expected_output = read_to_string("test-data/pure_segmentation_output.txt")
+            .expect("Failed to read expected output");
+        let output = read_to_string("test-data/pure_segmentation_output.tmp")
+            .expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn pure_paging() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/input.txt"),
+            Path::new("test-data/pure_paging_output.tmp"),
+            Options {
+                scheme: Scheme::PurePaging,
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process pure paging example");
+
+        let expected_output = read_to_string("test-data/pure_paging_output.txt")
+            .expect("Failed to read expected output");
+        let output =
+            read_to_string("test-data/pure_paging_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn geometry() {
+        process(
+            Path::new("test-data/geometry_init.txt"),
+            Path::new("test-data/geometry_input.txt"),
+            Path::new("test-data/geometry_output.tmp"),
+            Options {
+                geometry: Geometry::new(4, 4, 6, 16).expect("Failed to create Geometry"),
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process geometry example");
+
+        let expected_output = read_to_string("test-data/geometry_output.txt")
+            .expect("Failed to read expected output");
+        let output =
+            read_to_string("test-data/geometry_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn lines_format() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/input.txt"),
+            Path::new("test-data/lines_output.tmp"),
+            Options {
+                policy: Some(Box::new(Lru::new())),
+                format: OutputFormat::Lines,
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process lines example");
+
+        let expected_output = read_to_string("test-data/output.txt")
+            .expect("Failed to read expected output")
+            .replace(' ', "\n");
+        let output = read_to_string("test-data/lines_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn tlb() {
+        let virtual_memory = process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/tlb_input.txt"),
+            Path::new("test-data/tlb_output.tmp"),
+            Options {
+                tlb: Some(TlbConfig {
+                    entries: 4,
+                    associativity: 4,
+                    replacement: TlbReplacement::Lru,
+                }),
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process TLB example");
+
+        let expected_output =
+            read_to_string("test-data/tlb_output.txt").expect("Failed to read expected output");
+        let output = read_to_string("test-data/tlb_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+        assert_eq!(
+            virtual_memory.tlb_stats(),
+            Some(TlbStats { hits: 2, misses: 2 })
+        );
+    }
+
+    #[test]
+    fn trace() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/trace_input.txt"),
+            Path::new("test-data/trace_output.tmp"),
+            Options {
+                trace: Some(PathBuf::from("test-data/trace.tmp")),
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process trace example");
+
+        let expected_trace =
+            read_to_string("test-data/trace.txt").expect("Failed to read expected trace");
+        let trace = read_to_string("test-data/trace.tmp").expect("Failed to read trace");
+
+        assert_eq!(expected_trace, trace);
+    }
+
+    #[test]
+    fn json() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/trace_input.txt"),
+            Path::new("test-data/json_output.tmp"),
+            Options {
+                format: OutputFormat::Json,
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process JSON example");
+
+        let expected_output =
+            read_to_string("test-data/json_output.txt").expect("Failed to read expected output");
+        let output = read_to_string("test-data/json_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn json_lines() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/trace_input.txt"),
+            Path::new("test-data/jsonl_output.tmp"),
+            Options {
+                format: OutputFormat::JsonLines,
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process JSON Lines example");
+
+        let expected_output =
+            read_to_string("test-data/jsonl_output.txt").expect("Failed to read expected output");
+        let output = read_to_string("test-data/jsonl_output.tmp").expect("Failed to read output");
+
+        assert_eq!(expected_output, output);
+    }
+
+    #[test]
+    fn snapshot() {
+        let options = || Options {
+            policy: Some(Box::new(Lru::new())),
+            tlb: Some(TlbConfig {
+                entries: 4,
+                associativity: 4,
+                replacement: TlbReplacement::Lru,
+            }),
+            ..Options::default()
+        };
+
+        // Translating the first half, saving and restoring for the second
+        // half gives the same results as translating everything at once
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/trace_input.txt"),
+            Path::new("test-data/snapshot_expected.tmp"),
+            options(),
+        )
+        .expect("Failed to process snapshot example");
+        let expected_output =
+            read_to_string("test-data/snapshot_expected.tmp").expect("Failed to read output");
+
+        let input = read_to_string("test-data/trace_input.txt").expect("Failed to read input");
+        let addresses: Vec<&str> = input.split_whitespace().collect();
+        let (first, second) = addresses.split_at(addresses.len() / 2);
+        std::fs::write("test-data/snapshot_first.tmp", first.join(" "))
+            .expect("Failed to write input");
+        std::fs::write("test-data/snapshot_second.tmp", second.join(" "))
+            .expect("Failed to write input");
+
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/snapshot_first.tmp"),
+            Path::new("test-data/snapshot_first_output.tmp"),
+            Options {
+                snapshot: Some(PathBuf::from("test-data/snapshot.tmp")),
+                ..options()
+            },
+        )
+        .expect("Failed to save snapshot");
+        process(
+            Path::new("does-not-exist.txt"),
+            Path::new("test-data/snapshot_second.tmp"),
+            Path::new("test-data/snapshot_second_output.tmp"),
+            Options {
+                restore: Some(PathBuf::from("test-data/snapshot.tmp")),
+                ..Options::default()
+            },
+        )
+        .expect("Failed to restore snapshot");
+
+        let output = [
+            "test-data/snapshot_first_output.tmp",
+            "test-data/snapshot_second_output.tmp",
+        ]
+        .map(|path| read_to_string(path).expect("Failed to read output"))
+        .map(|output| output.trim_end().to_string())
+        .join(" ");
+
+        assert_eq!(expected_output.trim_end(), output);
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(
+            validate_init(Path::new("test-data/multi_init.txt"), &Geometry::default()),
+            Ok(vec![])
+        );
+
+        let diagnostics = validate_init(
+            Path::new("test-data/conflict_init.txt"),
+            &Geometry::default(),
+        )
+        .expect("Failed to validate init file");
+        let expected = read_to_string("test-data/conflict_diagnostics.txt")
+            .expect("Failed to read expected diagnostics");
+
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            expected.lines().collect::<Vec<_>>()
+        );
+
+        // Running or loading a conflicting init file fails on the first conflict
+        let error = process(
+            Path::new("test-data/conflict_init.txt"),
+            Path::new("test-data/input.txt"),
+            Path::new("test-data/conflict_output.tmp"),
+            Options::default(),
+        )
+        .map(|_| ())
+        .expect_err("Conflicting init file was accepted");
+        assert_eq!(error.root(), &VMError::InitConflict(diagnostics[0]));
+        assert_eq!(
+            display_chain(&error),
+            format!(
+                "test-data/conflict_init.txt: {}",
+                expected.lines().next().unwrap_or_default()
+            )
+        );
+        assert_eq!(
+            load(
+                Path::new("test-data/conflict_init.txt"),
+                &mut Options::default()
+            )
+            .map(|_| ()),
+            Err(error)
+        );
+
+        // Schemes building their tables from the pages alone only reject the
+        // pages sharing a frame
+        for scheme in [
+            Scheme::Radix { levels: 2 },
+            Scheme::Inverted,
+            Scheme::PurePaging,
+        ] {
+            let mut options = Options {
+                scheme,
+                ..Options::default()
+            };
+            let error = load(Path::new("test-data/conflict_init.txt"), &mut options)
+                .map(|_| ())
+                .expect_err("Pages sharing a frame were accepted");
+            assert_eq!(error.root(), &VMError::InitConflict(diagnostics[3]));
+        }
+
+        // Pure segmentation rejects overlapping segments while loading them
+        let mut options = Options {
+            scheme: Scheme::PureSegmentation,
+            ..Options::default()
+        };
+        let error = load(Path::new("test-data/overlap_init.txt"), &mut options)
+            .map(|_| ())
+            .expect_err("Overlapping segments were accepted");
+        assert_eq!(
+            display_chain(&error),
+            "test-data/overlap_init.txt: invalid frame number"
+        );
+    }
+
+    #[test]
+    fn paging_conflicts() {
+        for scheme in [
+            Scheme::Inverted,
+            Scheme::Radix { levels: 2 },
+            Scheme::PurePaging,
+        ] {
+            for (path, shared) in [
+                ("test-data/shared_frame_init.txt", "frame 7"),
+                ("test-data/shared_block_init.txt", "block 9"),
+            ] {
+                let mut options = Options {
+                    scheme,
+                    ..Options::default()
+                };
+                let error = load(Path::new(path), &mut options)
+                    .map(|_| ())
+                    .expect_err("Pages sharing a frame were accepted");
+
+                assert_eq!(error.root().code(), "init_conflict");
+                assert!(display_chain(&error).starts_with(path));
+                assert!(display_chain(&error).contains(shared));
+            }
+        }
+
+        // A page in the page table's frames fails in the context of the file
+        let mut options = Options {
+            scheme: Scheme::PurePaging,
+            ..Options::default()
+        };
+        let error = load(
+            Path::new("test-data/page_table_frame_init.txt"),
+            &mut options,
+        )
+        .map(|_| ())
+        .expect_err("Page in the page table was accepted");
+        assert_eq!(
+            display_chain(&error),
+            "test-data/page_table_frame_init.txt: invalid frame number"
+        );
+    }
+
+    #[test]
+    fn parse_triple_positions() {
+        let geometry = Geometry::default();
+        let st = |line| {
+            parse_triples(Path::new("init.txt"), 3, line, |segment, size, frame| {
+                STInput::new(segment, size, frame, &geometry)
+            })
+            .map(|triples| triples.len())
+        };
+        let error = |token, error| Err(parse_error(Path::new("init.txt"), 3, token, error));
+
+        assert_eq!(st(""), Ok(0));
+        assert_eq!(st("  8   4000\t3  "), Ok(1));
+        // Tokens are counted from 1 across the whole line
+        assert_eq!(st("8 4000 3 x 10 5"), error(4, VMError::InvalidSegment));
+        assert_eq!(st("8 4000 3 9 -1 5"), error(5, VMError::InvalidSegmentSize));
+        assert_eq!(st("8 4000 3 9 10 2000"), error(6, VMError::InvalidFrame));
+        // A trailing triple is incomplete from its first token
+        assert_eq!(st("8 4000 3 9"), error(4, VMError::IncompleteTriple));
+        assert_eq!(st("8 4000"), error(1, VMError::IncompleteTriple));
+
+        let protections = |line| {
+            parse_triples(Path::new("init.txt"), 3, line, |segment, page, mode| {
+                ProtectionInput::new(segment, page, mode, &geometry)
+            })
+            .map(|triples| triples.len())
+        };
+        assert_eq!(protections("8 * r-x 8 0 rw-"), Ok(2));
+        assert_eq!(
+            protections("8 * r-x 8 y rw-"),
+            error(5, VMError::InvalidPage)
+        );
+        assert_eq!(
+            protections("8 * r-x 8 0 rw"),
+            error(6, VMError::InvalidProtection)
+        );
+    }
+
+    #[test]
+    fn parse_address_errors() {
+        let geometry = Geometry::default();
+        let parse =
+            |token| parse_address(token, &geometry).map(|(process, address, _)| (process, address));
+
+        assert_eq!(parse("2097162"), Ok((None, 2_097_162)));
+        assert_eq!(parse("1:2097162"), Ok((Some(1), 2_097_162)));
+        assert_eq!(parse("x:2097162"), Err(VMError::InvalidProcess));
+        assert_eq!(parse("1:"), Err(VMError::InvalidAddress));
+        assert_eq!(parse("-5"), Err(VMError::InvalidAddress));
+        assert_eq!(parse("4294967296"), Err(VMError::InvalidAddress));
+        assert_eq!(parse("4294967295"), Err(VMError::VirtualAddressLeadingBits));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |file: &str, line, token, error| {
+            Err(VMError::Parse {
+                file: file.into(),
+                line,
+                token,
+                error: Box::new(error),
+            })
+        };
+
+        assert_eq!(
+            process(
+                Path::new("test-data/bad_init.txt"),
+                Path::new("test-data/input.txt"),
+                Path::new("test-data/bad_init_output.tmp"),
+                Options::default(),
+            )
+            .map(|_| ()),
+            error("test-data/bad_init.txt", 2, 5, VMError::InvalidPage)
+        );
+
+        assert_eq!(
+            process(
+                Path::new("test-data/incomplete_init.txt"),
+                Path::new("test-data/input.txt"),
+                Path::new("test-data/incomplete_output.tmp"),
+                Options::default(),
+            )
+            .map(|_| ()),
+            error(
+                "test-data/incomplete_init.txt",
+                6,
+                4,
+                VMError::IncompleteTriple
+            )
+        );
+
+        assert_eq!(
+            process(
+                Path::new("test-data/init.txt"),
+                Path::new("test-data/bad_input.txt"),
+                Path::new("test-data/bad_input_output.tmp"),
+                Options::default(),
+            )
+            .map(|_| ()),
+            error("test-data/bad_input.txt", 2, 2, VMError::InvalidAddress)
+        );
+
+        // Blank lines still count, and a process that does not exist is
+        // reported at its token
+        assert_eq!(
+            process(
+                Path::new("test-data/init.txt"),
+                Path::new("test-data/bad_process_input.txt"),
+                Path::new("test-data/bad_process_output.tmp"),
+                Options::default(),
+            )
+            .map(|_| ()),
+            error(
+                "test-data/bad_process_input.txt",
+                3,
+                2,
+                VMError::InvalidProcess
+            )
+        );
+
+        let missing = process(
+            Path::new("test-data/missing_init.txt"),
+            Path::new("test-data/input.txt"),
+            Path::new("test-data/missing_output.tmp"),
+            Options::default(),
+        )
+        .map(|_| ())
+        .expect_err("Missing init file was read");
+        assert!(matches!(
+            &missing,
+            VMError::File { path, error }
+                if path == Path::new("test-data/missing_init.txt")
+                    && matches!(**error, VMError::IOError(_))
+        ));
+    }
+
+    #[test]
+    fn stats() {
+        process(
+            Path::new("test-data/init.txt"),
+            Path::new("test-data/tlb_input.txt"),
+            Path::new("test-data/stats_output.tmp"),
+            Options {
+                tlb: Some(TlbConfig {
+                    entries: 4,
+                    associativity: 4,
+                    replacement: TlbReplacement::Lru,
+                }),
+                stats: Some(PathBuf::from("test-data/stats_summary.tmp")),
+                ..Options::default()
+            },
+        )
+        .expect("Failed to process stats example");
+
+        let expected_summary =
+            read_to_string("test-data/stats_summary.txt").expect("Failed to read expected summary");
+        let summary =
+            read_to_string("test-data/stats_summary.tmp").expect("Failed to read summary");
+
+        assert_eq!(expected_summary, summary);
+    }
 }
@@ -0,0 +1,356 @@
+use std::io::{BufRead, Write};
+
+use crate::process::summary;
+use virtual_memory::{
+    error::{display_chain, VMError, VMResult},
+    vm::{
+        frame::{EntryLocation, FrameKind},
+        scheme::Scheme,
+        virtual_address::VirtualAddress,
+        virtual_memory::VirtualMemory,
+    },
+};
+
+pub const HELP: &str = "\
+Commands:
+  translate <va>      Physical address of a virtual address
+  decode <va>         Split a virtual address into s, p, w and pw
+  read <va>           Read the word at a virtual address
+  write <va> <value>  Write a word at a virtual address
+  process <pid>       Switch to another process
+  fork                Copy the active process, sharing its pages until written
+  dump st             Segments in use by the active process
+  dump pt <seg>       Page table entries of a segment
+  dump frame <n>      Flags and non-zero words of a frame
+  free                Free frames and used and free disk blocks
+  holes               How the free frames are split into holes
+  compact             Close up the holes between segments (pure segmentation)
+  stats               Fault and TLB counters
+  help                Print this message
+  quit                Leave";
+
+/// Parses `field` as a number, naming `what` in the error.
+fn number<T: std::str::FromStr>(field: Option<&str>, what: &str) -> Result<T, String> {
+    let field = field.ok_or_else(|| format!("missing {what}"))?;
+    field
+        .parse()
+        .map_err(|_| format!("invalid {what} '{field}'"))
+}
+
+fn virtual_address(
+    virtual_memory: &VirtualMemory,
+    field: Option<&str>,
+) -> Result<VirtualAddress, String> {
+    VirtualAddress::new(number(field, "address")?, virtual_memory.geometry())
+        .map_err(|error| display_chain(&error))
+}
+
+/// Describes a table entry: a frame, a disk block or nothing.
+fn entry(value: i32) -> String {
+    match value {
+        0 => "not initialized".to_string(),
+        block if block < 0 => format!("block {}", block.unsigned_abs()),
+        frame => format!("frame {frame}"),
+    }
+}
+
+fn dump_st(virtual_memory: &VirtualMemory) -> VMResult<String> {
+    let mut lines = Vec::new();
+
+    for s in 0..virtual_memory.geometry().segment_count() {
+        let s = u16::try_from(s)?;
+        let (size, page_table) = virtual_memory.segment(s)?;
+        // Pure segmentation keeps the base address in place of the page table
+        let location = match (virtual_memory.scheme(), page_table) {
+            (Scheme::PureSegmentation, base) if base > 0 => format!("base address {base}"),
+            (Scheme::PureSegmentation, base) => format!("base {}", entry(base)),
+            (_, page_table) => format!("page table {}", entry(page_table)),
+        };
+        if size != 0 || page_table != 0 {
+            lines.push(format!("segment {s}: size {size}, {location}"));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn dump_pt(virtual_memory: &VirtualMemory, s: u16) -> VMResult<String> {
+    let mut lines = Vec::new();
+
+    for p in 0..virtual_memory.geometry().pages_per_segment() {
+        let p = u16::try_from(p)?;
+        let value = virtual_memory.page_entry(s, p)?;
+        if value != 0 {
+            lines.push(format!("page {p}: {}", entry(value)));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn dump_frame(virtual_memory: &VirtualMemory, n: usize) -> Result<String, String> {
+    let frame = virtual_memory
+        .frame(n)
+        .ok_or_else(|| format!("no frame {n}"))?;
+
+    let kind = match frame.kind {
+        FrameKind::SegmentTable => "segment table",
+        FrameKind::SegmentDirectory => "segment directory",
+        FrameKind::PageDirectory => "page directory",
+        FrameKind::InvertedTable => "inverted table",
+        FrameKind::Segment => "segment",
+        FrameKind::PageTable => "page table",
+        FrameKind::Page => "page",
+    };
+    let mut flags = vec![if frame.is_free() { "free" } else { kind }.to_string()];
+    match frame.owner {
+        Some(EntryLocation::Memory(address)) => flags.push(format!("owner at {address}")),
+        Some(EntryLocation::Disk { block, offset }) => {
+            flags.push(format!("owner at block {block} word {offset}"));
+        }
+        None => {}
+    }
+    if frame.is_shared() {
+        flags.push(format!("shared by {}", frame.refs));
+    }
+    if frame.referenced {
+        flags.push("referenced".to_string());
+    }
+    if frame.dirty {
+        flags.push("dirty".to_string());
+    }
+    if let Some(block) = frame.block {
+        flags.push(format!("home block {block}"));
+    }
+
+    let mut lines = vec![format!("frame {n}: {}", flags.join(", "))];
+    for (offset, word) in frame.data.iter().enumerate() {
+        if *word != 0 {
+            lines.push(format!("  {offset}: {word}"));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+/// Runs one command line, returning what to print.
+fn execute(virtual_memory: &mut VirtualMemory, line: &str) -> Result<String, String> {
+    let mut fields = line.split_whitespace();
+    let vm_error = |error: VMError| display_chain(&error);
+
+    let output = match (fields.next(), fields.next()) {
+        (Some("translate"), address) => {
+            let virtual_address = virtual_address(virtual_memory, address)?;
+            virtual_memory
+                .translate(virtual_address)
+                .map_err(vm_error)?
+                .to_string()
+        }
+        (Some("decode"), address) => {
+            let VirtualAddress { s, p, w, pw } = virtual_address(virtual_memory, address)?;
+            format!("s={s} p={p} w={w} pw={pw}")
+        }
+        (Some("read"), address) => {
+            let virtual_address = virtual_address(virtual_memory, address)?;
+            virtual_memory
+                .read_word(virtual_address)
+                .map_err(vm_error)?
+                .to_string()
+        }
+        (Some("write"), address) => {
+            let virtual_address = virtual_address(virtual_memory, address)?;
+            let value = number(fields.next(), "value")?;
+            virtual_memory
+                .write_word(virtual_address, value)
+                .map_err(vm_error)?;
+            String::new()
+        }
+        (Some("process"), process) => {
+            virtual_memory
+                .switch_to(number(process, "process")?)
+                .map_err(vm_error)?;
+            String::new()
+        }
+        (Some("fork"), None) => {
+            format!(
+                "forked process {}",
+                virtual_memory.fork().map_err(vm_error)?
+            )
+        }
+        (Some("dump"), Some("st")) => dump_st(virtual_memory).map_err(vm_error)?,
+        (Some("dump"), Some("pt")) => {
+            dump_pt(virtual_memory, number(fields.next(), "segment")?).map_err(vm_error)?
+        }
+        (Some("dump"), Some("frame")) => {
+            dump_frame(virtual_memory, number(fields.next(), "frame")?)?
+        }
+        (Some("free"), None) => format!(
+            "free frames: {} of {}\nused disk blocks: {}\nfree disk blocks: {}",
+            virtual_memory.free_frames(),
+            virtual_memory.geometry().frame_count(),
+            virtual_memory.used_disk_blocks(),
+            virtual_memory.free_disk_blocks()
+        ),
+        (Some("holes"), None) => virtual_memory.fragmentation().to_string(),
+        (Some("compact"), None) => format!(
+            "moved {} frames",
+            virtual_memory.compact().map_err(vm_error)?
+        ),
+        (Some("stats"), None) => summary(virtual_memory),
+        (Some("help"), None) => HELP.to_string(),
+        (Some(command), _) => return Err(format!("unknown command '{command}', try help")),
+        (None, _) => String::new(),
+    };
+
+    if fields.next().is_some() {
+        return Err("too many arguments".to_string());
+    }
+
+    Ok(output)
+}
+
+/// Reads commands from `input` until it ends or `quit`, writing results to `output`.
+///
+/// With `prompt`, a prompt is written before each command.
+pub fn run(
+    virtual_memory: &mut VirtualMemory,
+    input: impl BufRead,
+    output: &mut impl Write,
+    prompt: bool,
+) -> VMResult<()> {
+    let show_prompt = |output: &mut dyn Write| -> VMResult<()> {
+        if prompt {
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    };
+
+    show_prompt(output)?;
+    for line in input.lines() {
+        let line = line?;
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+
+        match execute(virtual_memory, &line) {
+            Ok(result) if result.is_empty() => {}
+            Ok(result) => writeln!(output, "{result}")?,
+            Err(message) => writeln!(output, "error: {message}")?,
+        }
+        show_prompt(output)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::process::{load, Options};
+
+    use super::*;
+
+    fn repl(commands: &str) -> String {
+        let mut virtual_memory = load(Path::new("test-data/init.txt"), &mut Options::default())
+            .expect("Failed to load init file");
+        let mut output = Vec::new();
+        run(&mut virtual_memory, commands.as_bytes(), &mut output, false)
+            .expect("Failed to run commands");
+
+        String::from_utf8(output).expect("Output is not UTF-8")
+    }
+
+    #[test]
+    fn translate_and_decode() {
+        assert_eq!(
+            repl("translate 2097162\ndecode 2097162\ntranslate 2101152\n"),
+            "5130\ns=8 p=0 w=10 pw=10\nerror: virtual address is out of bounds\n"
+        );
+    }
+
+    #[test]
+    fn read_write() {
+        assert_eq!(
+            repl("write 2359818 42\nread 2359818\nquit\nread 2359818\n"),
+            "42\n"
+        );
+    }
+
+    #[test]
+    fn fork() {
+        assert_eq!(
+            repl("fork\nprocess 1\nwrite 2097162 5\nread 2097162\nprocess 0\nread 2097162\ndump frame 13\n"),
+            "forked process 1\n5\n0\nframe 13: page, owner at block 7 word 0, shared by 2\n"
+        );
+    }
+
+    #[test]
+    fn dump() {
+        assert_eq!(
+            repl("dump st\ndump pt 9\ndump frame 3\n"),
+            "segment 8: size 4000, page table frame 3\n\
+             segment 9: size 5000, page table block 7\n\
+             page 0: frame 13\n\
+             page 1: block 25\n\
+             frame 3: page table, owner at 17\n  \
+               0: 10\n  \
+               1: -20\n"
+        );
+    }
+
+    #[test]
+    fn holes_and_compact() {
+        let mut virtual_memory = load(
+            Path::new("test-data/init.txt"),
+            &mut Options {
+                scheme: Scheme::PureSegmentation,
+                ..Options::default()
+            },
+        )
+        .expect("Failed to load init file");
+        let mut output = Vec::new();
+        run(
+            &mut virtual_memory,
+            "translate 2359306\nholes\ncompact\nholes\ntranslate 2359306\n".as_bytes(),
+            &mut output,
+            false,
+        )
+        .expect("Failed to run commands");
+
+        assert_eq!(
+            String::from_utf8(output).expect("Output is not UTF-8"),
+            "5642\n\
+             holes: 2, largest hole: 1003 frames, free frames: 1004\n\
+             moved 18 frames\n\
+             holes: 1, largest hole: 1004 frames, free frames: 1004\n\
+             5130\n"
+        );
+        assert_eq!(
+            repl("compact\n"),
+            "error: not supported by the translation scheme\n"
+        );
+    }
+
+    #[test]
+    fn free_and_stats() {
+        let output = repl("translate 2359306\nfree\nstats\n");
+
+        assert!(output
+            .contains("free frames: 1018 of 1024\nused disk blocks: 4\nfree disk blocks: 1020\n"));
+        assert!(output.contains("page table faults: 1\n"));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            repl("bogus\ntranslate\ntranslate x\ndump frame 2000\ndecode 1 2\n\n"),
+            "error: unknown command 'bogus', try help\n\
+             error: missing address\n\
+             error: invalid address 'x'\n\
+             error: no frame 2000\n\
+             error: too many arguments\n"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crate::{
+    constants::SEGMENT_WORD_COUNT, geometry::Geometry, io::protection_input::ProtectionInput,
+    vm::protection::Permissions,
+};
+
+/// Per-process state: the frames holding its segment table and its protections.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AddressSpace {
+    /// Frames holding the segment table, or the single frame holding its
+    /// directory when the segment table is paged. With pure paging, frames
+    /// holding the page table instead.
+    pub segment_table: Vec<usize>,
+    pub page_size: usize,
+    pub segment_permissions: Vec<Permissions>,
+    pub page_permissions: HashMap<(u16, u16), Permissions>,
+}
+
+impl AddressSpace {
+    #[must_use]
+    pub fn new(segment_table: Vec<usize>, geometry: &Geometry) -> Self {
+        Self {
+            segment_table,
+            page_size: geometry.page_size(),
+            segment_permissions: vec![Permissions::ALL; geometry.segment_count()],
+            page_permissions: HashMap::new(),
+        }
+    }
+
+    /// Physical address of word `offset` of segment `s`'s entry.
+    #[must_use]
+    pub fn segment_entry(&self, s: u16, offset: usize) -> usize {
+        self.table_entry(usize::from(s) * SEGMENT_WORD_COUNT + offset)
+    }
+
+    /// Physical address of word `index` of the table spread over the frames
+    /// of `segment_table`.
+    #[must_use]
+    pub fn table_entry(&self, index: usize) -> usize {
+        self.segment_table[index / self.page_size] * self.page_size + index % self.page_size
+    }
+
+    /// With a paged segment table, physical address of the directory entry
+    /// for the page holding word `offset` of segment `s`'s entry, and the
+    /// word's offset within that page.
+    #[must_use]
+    pub fn directory_entry(&self, s: u16, offset: usize) -> (usize, usize) {
+        let index = usize::from(s) * SEGMENT_WORD_COUNT + offset;
+
+        (
+            self.segment_table[0] * self.page_size + index / self.page_size,
+            index % self.page_size,
+        )
+    }
+
+    /// Applies segment and page protections. Anything not listed stays `rwx`.
+    pub fn protect(&mut self, protection_init: Vec<ProtectionInput>) {
+        for input in protection_init {
+            match input.page {
+                Some(page) => {
+                    self.page_permissions
+                        .insert((input.segment, page), input.protection);
+                }
+                None => self.segment_permissions[usize::from(input.segment)] = input.protection,
+            }
+        }
+    }
+
+    /// Drops the protections of segment `s` and its pages, leaving it `rwx`.
+    pub fn clear_protection(&mut self, s: u16) {
+        self.segment_permissions[usize::from(s)] = Permissions::ALL;
+        self.page_permissions
+            .retain(|&(segment, _), _| segment != s);
+    }
+
+    /// Effective permissions of page `p` in segment `s`: the rights granted by
+    /// both the segment and the page.
+    #[must_use]
+    pub fn permissions(&self, s: u16, p: u16) -> Permissions {
+        let page = self
+            .page_permissions
+            .get(&(s, p))
+            .copied()
+            .unwrap_or_default();
+
+        self.segment_permissions[usize::from(s)].intersection(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_entry() {
+        let address_space = AddressSpace::new(vec![6, 4], &Geometry::default());
+
+        assert_eq!(address_space.segment_entry(0, 0), 6 * 512);
+        assert_eq!(address_space.segment_entry(8, 1), 6 * 512 + 17);
+        assert_eq!(address_space.segment_entry(256, 0), 4 * 512);
+        assert_eq!(address_space.segment_entry(511, 1), 5 * 512 - 1);
+    }
+
+    #[test]
+    fn segment_entry_large_pages() {
+        let geometry = Geometry::new(10, 10, 12, 64).expect("Failed to create Geometry");
+        let address_space = AddressSpace::new(vec![3], &geometry);
+
+        assert_eq!(address_space.segment_entry(1023, 1), 3 * 4096 + 2047);
+    }
+
+    #[test]
+    fn table_entry() {
+        let address_space = AddressSpace::new(vec![6, 4], &Geometry::default());
+
+        assert_eq!(address_space.table_entry(17), 6 * 512 + 17);
+        assert_eq!(address_space.table_entry(600), 4 * 512 + 88);
+    }
+
+    #[test]
+    fn directory_entry() {
+        let address_space = AddressSpace::new(vec![6], &Geometry::default());
+
+        assert_eq!(address_space.directory_entry(8, 1), (6 * 512, 17));
+        assert_eq!(address_space.directory_entry(511, 1), (6 * 512 + 1, 511));
+    }
+
+    #[test]
+    fn clear_protection() {
+        let geometry = Geometry::default();
+        let mut address_space = AddressSpace::new(vec![0, 1], &geometry);
+        address_space.protect(vec![
+            ProtectionInput::new("8", "*", "r-x", &geometry)
+                .expect("Failed to create ProtectionInput"),
+            ProtectionInput::new("8", "1", "r--", &geometry)
+                .expect("Failed to create ProtectionInput"),
+            ProtectionInput::new("9", "0", "---", &geometry)
+                .expect("Failed to create ProtectionInput"),
+        ]);
+
+        address_space.clear_protection(8);
+
+        assert_eq!(address_space.permissions(8, 1), Permissions::ALL);
+        assert_eq!(address_space.permissions(9, 0), Permissions::NONE);
+    }
+}
@@ -0,0 +1,265 @@
+use std::{fmt, ops::Range};
+
+use crate::vm::physical_memory::PhysicalMemory;
+
+/// Which hole long enough for a request the contiguous allocator takes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Fit {
+    /// The lowest one.
+    #[default]
+    First,
+    /// The shortest one, leaving the smallest hole behind.
+    Best,
+    /// The longest one, leaving the largest hole behind.
+    Worst,
+    /// The first one from where the previous allocation ended, wrapping
+    /// around to the start of memory.
+    Next,
+}
+
+/// Names accepted by `from_name`.
+pub const FIT_NAMES: [&str; 4] = ["first", "best", "worst", "next"];
+
+impl Fit {
+    /// Short name used in reports.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::First => "first",
+            Self::Best => "best",
+            Self::Worst => "worst",
+            Self::Next => "next",
+        }
+    }
+}
+
+/// Parses a fit as returned by `Fit::name`.
+#[must_use]
+pub fn from_name(name: &str) -> Option<Fit> {
+    match name {
+        "first" => Some(Fit::First),
+        "best" => Some(Fit::Best),
+        "worst" => Some(Fit::Worst),
+        "next" => Some(Fit::Next),
+        _ => None,
+    }
+}
+
+/// How free frames are split into holes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct FragmentationReport {
+    /// Frames in the longest hole, the largest request that can succeed.
+    pub largest_hole: usize,
+    pub hole_count: usize,
+    /// Free frames over all holes.
+    pub total_free: usize,
+}
+
+impl fmt::Display for FragmentationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "holes: {}, largest hole: {} frames, free frames: {}",
+            self.hole_count, self.largest_hole, self.total_free
+        )
+    }
+}
+
+/// Runs of free frames in `memory`, lowest first. Frame 0 is left out since
+/// a segment based there would read as not initialized.
+#[must_use]
+pub fn holes(memory: &PhysicalMemory) -> Vec<Range<usize>> {
+    let mut holes = Vec::new();
+    let mut start = None;
+
+    for (i, frame) in memory.iter().enumerate().skip(1) {
+        match (frame.is_free(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(first)) => {
+                holes.push(first..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(first) = start {
+        holes.push(first..memory.len());
+    }
+
+    holes
+}
+
+/// Largest hole, number of holes and free frames of `memory`.
+#[must_use]
+pub fn fragmentation(memory: &PhysicalMemory) -> FragmentationReport {
+    holes(memory)
+        .into_iter()
+        .fold(FragmentationReport::default(), |report, hole| {
+            FragmentationReport {
+                largest_hole: report.largest_hole.max(hole.len()),
+                hole_count: report.hole_count + 1,
+                total_free: report.total_free + hole.len(),
+            }
+        })
+}
+
+/// Places runs of contiguous frames in the holes of physical memory.
+///
+/// The allocator only picks the frames, marking them taken is left to the
+/// caller.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ContiguousAllocator {
+    fit: Fit,
+    /// Frame the next search starts from with `Fit::Next`.
+    cursor: usize,
+}
+
+impl ContiguousAllocator {
+    #[must_use]
+    pub const fn new(fit: Fit) -> Self {
+        Self { fit, cursor: 0 }
+    }
+
+    /// Rebuilds an allocator whose next search starts from `cursor`.
+    #[must_use]
+    pub const fn restore(fit: Fit, cursor: usize) -> Self {
+        Self { fit, cursor }
+    }
+
+    #[must_use]
+    pub const fn fit(&self) -> Fit {
+        self.fit
+    }
+
+    #[must_use]
+    pub const fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// First frame of a run of `count` free frames in `memory`, chosen by
+    /// the allocator's fit. `None` if no hole is long enough, however many
+    /// frames are free in total.
+    pub fn allocate(&mut self, memory: &PhysicalMemory, count: usize) -> Option<usize> {
+        let holes = holes(memory);
+        let long_enough = || holes.iter().filter(|hole| hole.len() >= count);
+
+        let start = match self.fit {
+            Fit::First => long_enough().next()?.start,
+            Fit::Best => long_enough().min_by_key(|hole| hole.len())?.start,
+            Fit::Worst => long_enough().max_by_key(|hole| hole.len())?.start,
+            // Within its hole, a next fit run starts where the previous one
+            // ended, and holes with too little room past that are skipped
+            Fit::Next => {
+                holes
+                    .iter()
+                    .map(|hole| hole.start.max(self.cursor)..hole.end)
+                    .find(|room| room.len() >= count)
+                    .or_else(|| long_enough().next().cloned())?
+                    .start
+            }
+        };
+        self.cursor = start + count;
+
+        Some(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{geometry::Geometry, vm::physical_memory::PhysicalMemoryTrait};
+
+    use super::*;
+
+    /// 16 frames leaving holes 1..3, 5..6, 8..12 and 14..16.
+    fn memory() -> PhysicalMemory {
+        let geometry = Geometry::new(2, 2, 2, 16).expect("Failed to create Geometry");
+        let mut memory = PhysicalMemory::new_memory(&geometry);
+        for taken in [0, 3, 4, 6, 7, 12, 13] {
+            memory[taken].refs = 1;
+        }
+        memory
+    }
+
+    #[test]
+    fn names() {
+        for name in FIT_NAMES {
+            let fit = from_name(name).expect("Failed to parse fit");
+            assert_eq!(fit.name(), name);
+        }
+
+        assert!(from_name("random").is_none());
+    }
+
+    #[test]
+    fn holes_and_fragmentation() {
+        let memory = memory();
+
+        assert_eq!(holes(&memory), vec![1..3, 5..6, 8..12, 14..16]);
+        assert_eq!(
+            fragmentation(&memory),
+            FragmentationReport {
+                largest_hole: 4,
+                hole_count: 4,
+                total_free: 9,
+            }
+        );
+    }
+
+    #[test]
+    fn fits() {
+        let memory = memory();
+        let allocate = |fit, count| ContiguousAllocator::new(fit).allocate(&memory, count);
+
+        assert_eq!(allocate(Fit::First, 2), Some(1));
+        assert_eq!(allocate(Fit::Best, 2), Some(1));
+        assert_eq!(allocate(Fit::Best, 1), Some(5));
+        assert_eq!(allocate(Fit::Worst, 1), Some(8));
+        for fit in [Fit::First, Fit::Best, Fit::Worst, Fit::Next] {
+            assert_eq!(allocate(fit, 5), None);
+        }
+    }
+
+    #[test]
+    fn next_fit() {
+        let mut memory = memory();
+        let mut allocator = ContiguousAllocator::new(Fit::Next);
+
+        assert_eq!(allocator.allocate(&memory, 1), Some(1));
+        memory[1].refs = 1;
+        // Carries on in the same hole, then moves past it
+        assert_eq!(allocator.allocate(&memory, 1), Some(2));
+        memory[2].refs = 1;
+        assert_eq!(allocator.allocate(&memory, 2), Some(8));
+        memory[8].refs = 1;
+        memory[9].refs = 1;
+        assert_eq!(allocator.allocate(&memory, 2), Some(10));
+        memory[10].refs = 1;
+        memory[11].refs = 1;
+        assert_eq!(allocator.allocate(&memory, 2), Some(14));
+        memory[14].refs = 1;
+        memory[15].refs = 1;
+
+        // Wraps around to the start of memory
+        assert_eq!(allocator.allocate(&memory, 1), Some(5));
+        assert_eq!(allocator.cursor(), 6);
+    }
+
+    #[test]
+    fn next_fit_skips_short_room() {
+        let memory = memory();
+
+        // Hole 8..12 has room for 2 frames past frame 10 but only 1 past 11,
+        // so the run goes to the next hole rather than back to frame 8
+        let mut allocator = ContiguousAllocator::restore(Fit::Next, 10);
+        assert_eq!(allocator.allocate(&memory, 2), Some(10));
+        let mut allocator = ContiguousAllocator::restore(Fit::Next, 11);
+        assert_eq!(allocator.allocate(&memory, 2), Some(14));
+        assert_eq!(allocator.cursor(), 16);
+
+        // Wraps around once no hole past the cursor is long enough
+        let mut allocator = ContiguousAllocator::restore(Fit::Next, 10);
+        assert_eq!(allocator.allocate(&memory, 3), Some(8));
+        let mut allocator = ContiguousAllocator::restore(Fit::Next, 15);
+        assert_eq!(allocator.allocate(&memory, 2), Some(1));
+    }
+}
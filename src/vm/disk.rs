@@ -0,0 +1,119 @@
+use std::ops::{Index, IndexMut};
+
+use crate::{
+    error::{VMError, VMResult},
+    geometry::Geometry,
+};
+
+/// Swap space: one block per frame, each the size of a page, with a record of
+/// which blocks hold something.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Disk {
+    blocks: Vec<Vec<i32>>,
+    used: Vec<bool>,
+}
+
+impl Disk {
+    #[must_use]
+    pub fn new(geometry: &Geometry) -> Self {
+        let mut used = vec![false; geometry.frame_count()];
+
+        // Block 0 can never be referenced since a 0 entry means not initialized
+        used[0] = true;
+
+        Self {
+            blocks: vec![vec![0; geometry.page_size()]; geometry.frame_count()],
+            used,
+        }
+    }
+
+    /// Takes the lowest free block.
+    ///
+    /// # Errors
+    /// - `VMError::DiskFull` if every block is in use.
+    pub fn allocate(&mut self) -> VMResult<usize> {
+        let block = self
+            .used
+            .iter()
+            .position(|used| !used)
+            .ok_or(VMError::DiskFull)?;
+        self.used[block] = true;
+
+        Ok(block)
+    }
+
+    /// Marks `block` as in use, for blocks named by an init file or snapshot.
+    pub fn claim(&mut self, block: usize) {
+        self.used[block] = true;
+    }
+
+    /// Returns `block` to the free blocks. Its contents are left as they are
+    /// and must not be read until it is allocated and written again.
+    pub fn free(&mut self, block: usize) {
+        // Block 0 stays reserved
+        if block != 0 {
+            self.used[block] = false;
+        }
+    }
+
+    #[must_use]
+    pub fn is_used(&self, block: usize) -> bool {
+        self.used[block]
+    }
+
+    /// Blocks in use, including the reserved block 0.
+    pub fn used_blocks(&self) -> impl Iterator<Item = usize> + '_ {
+        self.used
+            .iter()
+            .enumerate()
+            .filter(|(_, &used)| used)
+            .map(|(block, _)| block)
+    }
+
+    #[must_use]
+    pub fn free_count(&self) -> usize {
+        self.used.iter().filter(|&&used| !used).count()
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &[i32]> {
+        self.blocks.iter().map(Vec::as_slice)
+    }
+}
+
+impl Index<usize> for Disk {
+    type Output = [i32];
+
+    fn index(&self, block: usize) -> &Self::Output {
+        &self.blocks[block]
+    }
+}
+
+impl IndexMut<usize> for Disk {
+    fn index_mut(&mut self, block: usize) -> &mut Self::Output {
+        &mut self.blocks[block]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocate_and_free() {
+        let geometry = Geometry::new(2, 2, 2, 4).expect("Failed to create Geometry");
+        let mut disk = Disk::new(&geometry);
+        disk.claim(2);
+
+        assert_eq!(disk.allocate(), Ok(1));
+        assert_eq!(disk.allocate(), Ok(3));
+        assert_eq!(disk.allocate(), Err(VMError::DiskFull));
+        assert_eq!(disk.free_count(), 0);
+
+        disk.free(2);
+        disk.free(0);
+        assert!(!disk.is_used(2));
+        assert!(disk.is_used(0));
+        assert_eq!(disk.used_blocks().collect::<Vec<_>>(), vec![0, 1, 3]);
+        assert_eq!(disk.allocate(), Ok(2));
+    }
+}
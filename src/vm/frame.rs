@@ -1,16 +1,69 @@
-use crate::constants::PAGE_SIZE;
+/// What a frame currently holds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FrameKind {
+    SegmentTable,
+    /// Root of a paged segment table, pointing to its pages.
+    SegmentDirectory,
+    /// Root of a process's radix page tables, or part of its single page
+    /// table with pure paging.
+    PageDirectory,
+    /// Part of the inverted page table every process shares.
+    InvertedTable,
+    /// Part of a segment placed whole in contiguous frames by pure
+    /// segmentation.
+    Segment,
+    PageTable,
+    Page,
+}
 
+/// Location of the table entry that maps a frame.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EntryLocation {
+    /// Physical address of the entry.
+    Memory(usize),
+    /// Word `offset` of a page table stored in disk block `block`.
+    Disk { block: usize, offset: usize },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Frame {
-    pub free: bool,
-    pub data: [i32; PAGE_SIZE],
+    /// Page table entries mapping the frame, 1 for a segment or page table
+    /// and 0 while the frame is free. Pages shared by `fork` count every
+    /// process mapping them.
+    pub refs: u32,
+    pub kind: FrameKind,
+    pub owner: Option<EntryLocation>,
+    /// Set on every access, cleared by the replacement policy.
+    pub referenced: bool,
+    /// Set when the frame differs from its copy on disk.
+    pub dirty: bool,
+    /// Disk block holding the backing copy of the frame, if any.
+    pub block: Option<usize>,
+    pub data: Vec<i32>,
 }
 
 impl Frame {
-    pub const fn new() -> Self {
+    #[must_use]
+    pub fn new(page_size: usize) -> Self {
         Self {
-            free: true,
-            data: [0; PAGE_SIZE],
+            refs: 0,
+            kind: FrameKind::Page,
+            owner: None,
+            referenced: false,
+            dirty: false,
+            block: None,
+            data: vec![0; page_size],
         }
     }
+
+    #[must_use]
+    pub const fn is_free(&self) -> bool {
+        self.refs == 0
+    }
+
+    /// Whether more than one page table entry maps the frame.
+    #[must_use]
+    pub const fn is_shared(&self) -> bool {
+        self.refs > 1
+    }
 }
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::{
+    constants::{
+        INVERTED_NEXT_OFFSET, INVERTED_PAGE_OFFSET, INVERTED_PROCESS_OFFSET, INVERTED_WORD_COUNT,
+    },
+    geometry::Geometry,
+    vm::{
+        physical_memory::{PhysicalMemory, PhysicalMemoryTrait},
+        virtual_memory::ProcessId,
+    },
+};
+
+/// A page of a process: its id, segment and page number.
+pub type PageKey = (ProcessId, u16, u16);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct InvertedStats {
+    /// Frames taken by the table, hash anchors included.
+    pub table_frames: usize,
+    pub lookups: u64,
+    /// Entries compared over all lookups.
+    pub probes: u64,
+}
+
+impl InvertedStats {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average_probe_length(&self) -> f64 {
+        if self.lookups == 0 {
+            0.0
+        } else {
+            self.probes as f64 / self.lookups as f64
+        }
+    }
+}
+
+/// Hashed inverted page table shared by every process, stored in the first
+/// frames of physical memory.
+///
+/// The table starts with one hash anchor per frame holding the first frame of
+/// its chain, followed by one entry per frame with the process and page
+/// number it holds and the next frame in the chain. The table itself sits in
+/// frame 0, so 0 ends a chain. Only resident pages are in the table, the
+/// blocks of pages on disk are kept apart like an operating system would.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InvertedTable {
+    frame_count: usize,
+    page_bits: usize,
+    swapped: HashMap<PageKey, usize>,
+    stats: InvertedStats,
+}
+
+impl InvertedTable {
+    #[must_use]
+    pub fn new(geometry: &Geometry) -> Self {
+        Self {
+            frame_count: geometry.frame_count(),
+            page_bits: geometry.page_bits(),
+            swapped: HashMap::new(),
+            stats: InvertedStats {
+                table_frames: Self::table_frames(geometry),
+                ..InvertedStats::default()
+            },
+        }
+    }
+
+    /// Frames the anchors and entries of a table for `geometry` take.
+    #[must_use]
+    pub const fn table_frames(geometry: &Geometry) -> usize {
+        (geometry.frame_count() * (1 + INVERTED_WORD_COUNT)).div_ceil(geometry.page_size())
+    }
+
+    /// Rebuilds an `InvertedTable` with the counters returned by `stats`.
+    /// Pages on disk are added back with `swap_out`.
+    #[must_use]
+    pub fn restore(geometry: &Geometry, stats: InvertedStats) -> Self {
+        Self {
+            stats: InvertedStats {
+                table_frames: Self::table_frames(geometry),
+                ..stats
+            },
+            ..Self::new(geometry)
+        }
+    }
+
+    #[must_use]
+    pub const fn stats(&self) -> InvertedStats {
+        self.stats
+    }
+
+    /// Pages on disk and their blocks, in no particular order.
+    pub fn swapped(&self) -> impl Iterator<Item = (PageKey, usize)> + '_ {
+        self.swapped.iter().map(|(&key, &block)| (key, block))
+    }
+
+    /// Records that page `key` is on disk in `block`.
+    pub fn swap_out(&mut self, key: PageKey, block: usize) {
+        self.swapped.insert(key, block);
+    }
+
+    /// Block holding page `key`, if it is on disk.
+    #[must_use]
+    pub fn swapped_block(&self, key: PageKey) -> Option<usize> {
+        self.swapped.get(&key).copied()
+    }
+
+    /// Takes the block holding page `key`, if it is on disk.
+    pub fn swap_in(&mut self, key: PageKey) -> Option<usize> {
+        self.swapped.remove(&key)
+    }
+
+    fn page_number(&self, s: u16, p: u16) -> usize {
+        usize::from(s) << self.page_bits | usize::from(p)
+    }
+
+    /// Anchor of the chain holding page `key`.
+    fn anchor(&self, (process, s, p): PageKey) -> usize {
+        let key = (process as u64) << 32 | self.page_number(s, p) as u64;
+        // Fibonacci hashing spreads neighbouring pages over the anchors
+        let hash = key.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32;
+
+        usize::try_from(hash).unwrap_or_default() % self.frame_count
+    }
+
+    /// Address of the first word of `frame`'s entry.
+    const fn entry(&self, frame: usize) -> usize {
+        self.frame_count + frame * INVERTED_WORD_COUNT
+    }
+
+    fn next(memory: &PhysicalMemory, address: usize) -> usize {
+        usize::try_from(memory.get_word_by_address(address)).unwrap_or_default()
+    }
+
+    /// Frame holding page `key`, following its chain from the anchor.
+    pub fn lookup(&mut self, memory: &PhysicalMemory, key: PageKey) -> Option<usize> {
+        let (process, s, p) = key;
+        self.stats.lookups += 1;
+
+        let mut frame = Self::next(memory, self.anchor(key));
+        while frame != 0 {
+            self.stats.probes += 1;
+            let entry = self.entry(frame);
+            if Self::next(memory, entry + INVERTED_PROCESS_OFFSET) == process
+                && Self::next(memory, entry + INVERTED_PAGE_OFFSET) == self.page_number(s, p)
+            {
+                return Some(frame);
+            }
+            frame = Self::next(memory, entry + INVERTED_NEXT_OFFSET);
+        }
+
+        None
+    }
+
+    /// Maps `frame` to page `key` at the head of its chain.
+    pub fn insert(&self, memory: &mut PhysicalMemory, frame: usize, key: PageKey) {
+        let (process, s, p) = key;
+        let anchor = self.anchor(key);
+        let entry = self.entry(frame);
+        let to_word = |value: usize| i32::try_from(value).unwrap_or_default();
+
+        memory.set_word_by_address(entry + INVERTED_PROCESS_OFFSET, to_word(process));
+        memory.set_word_by_address(
+            entry + INVERTED_PAGE_OFFSET,
+            to_word(self.page_number(s, p)),
+        );
+        memory.set_word_by_address(
+            entry + INVERTED_NEXT_OFFSET,
+            memory.get_word_by_address(anchor),
+        );
+        memory.set_word_by_address(anchor, to_word(frame));
+    }
+
+    /// Unlinks `frame` from its chain, returning the page it held.
+    pub fn remove(&self, memory: &mut PhysicalMemory, frame: usize) -> PageKey {
+        let entry = self.entry(frame);
+        let process = Self::next(memory, entry + INVERTED_PROCESS_OFFSET);
+        let page_number = Self::next(memory, entry + INVERTED_PAGE_OFFSET);
+        let page_mask = (1 << self.page_bits) - 1;
+        let key = (
+            process,
+            u16::try_from(page_number >> self.page_bits).unwrap_or_default(),
+            u16::try_from(page_number & page_mask).unwrap_or_default(),
+        );
+
+        // Find the word pointing at the frame: the anchor or a previous entry
+        let mut link = self.anchor(key);
+        while Self::next(memory, link) != frame {
+            link = self.entry(Self::next(memory, link)) + INVERTED_NEXT_OFFSET;
+        }
+        memory.set_word_by_address(
+            link,
+            memory.get_word_by_address(entry + INVERTED_NEXT_OFFSET),
+        );
+        for offset in 0..INVERTED_WORD_COUNT {
+            memory.set_word_by_address(entry + offset, 0);
+        }
+
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chains() {
+        // 16 frames of 16 words, the table takes 4
+        let geometry = Geometry::new(2, 2, 4, 16).expect("Failed to create Geometry");
+        let mut memory = PhysicalMemory::new_memory(&geometry);
+        let mut table = InvertedTable::new(&geometry);
+        assert_eq!(table.stats().table_frames, 4);
+
+        // Fill every frame so chains have to form
+        let keys: Vec<PageKey> = (4..16)
+            .map(|frame| {
+                let s = u16::try_from(frame / 4).unwrap_or_default();
+                let p = u16::try_from(frame / 2 % 2).unwrap_or_default();
+                (frame % 2, s, p)
+            })
+            .collect();
+        for (frame, &key) in (4..16).zip(&keys) {
+            table.insert(&mut memory, frame, key);
+        }
+        for (frame, &key) in (4..16).zip(&keys) {
+            assert_eq!(table.lookup(&memory, key), Some(frame));
+        }
+        assert_eq!(table.lookup(&memory, (2, 0, 0)), None);
+        assert!(table.stats().average_probe_length() >= 1.0);
+
+        assert_eq!(table.remove(&mut memory, 9), keys[5]);
+        assert_eq!(table.lookup(&memory, keys[5]), None);
+        for (frame, &key) in (4..16).zip(&keys).filter(|(frame, _)| *frame != 9) {
+            assert_eq!(table.lookup(&memory, key), Some(frame));
+        }
+
+        table.swap_out(keys[5], 3);
+        assert_eq!(table.swapped_block(keys[5]), Some(3));
+        assert_eq!(table.swap_in(keys[5]), Some(3));
+        assert_eq!(table.swap_in(keys[5]), None);
+    }
+}
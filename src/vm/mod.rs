@@ -1,4 +1,14 @@
-mod frame;
+mod address_space;
+pub mod allocator;
+pub mod disk;
+pub mod frame;
+pub mod inverted;
 mod physical_memory;
+pub mod protection;
+pub mod replacement;
+pub mod scheme;
+pub mod stats;
+pub mod tlb;
+pub mod trace;
 pub mod virtual_address;
 pub mod virtual_memory;
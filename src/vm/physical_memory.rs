@@ -1,4 +1,4 @@
-use crate::constants::{PAGE_COUNT, PAGE_SIZE};
+use crate::geometry::Geometry;
 
 use super::frame::Frame;
 
@@ -6,7 +6,8 @@ pub type PhysicalMemory = Vec<Frame>;
 
 #[allow(clippy::module_name_repetitions)]
 pub trait PhysicalMemoryTrait {
-    fn new_memory() -> Self;
+    fn new_memory(geometry: &Geometry) -> Self;
+    fn page_size(&self) -> usize;
     fn get_word_by_address(&self, address: usize) -> i32;
     fn get_word_by_offset(&self, frame: usize, offset: usize) -> i32;
     fn set_word_by_address(&mut self, address: usize, value: i32);
@@ -14,12 +15,17 @@ pub trait PhysicalMemoryTrait {
 }
 
 impl PhysicalMemoryTrait for PhysicalMemory {
-    fn new_memory() -> Self {
-        vec![Frame::new(); PAGE_COUNT]
+    fn new_memory(geometry: &Geometry) -> Self {
+        vec![Frame::new(geometry.page_size()); geometry.frame_count()]
+    }
+
+    fn page_size(&self) -> usize {
+        self.first().map_or(0, |frame| frame.data.len())
     }
 
     fn get_word_by_address(&self, address: usize) -> i32 {
-        self[address / PAGE_SIZE].data[address % PAGE_SIZE]
+        let page_size = self.page_size();
+        self[address / page_size].data[address % page_size]
     }
 
     fn get_word_by_offset(&self, frame: usize, offset: usize) -> i32 {
@@ -27,7 +33,8 @@ impl PhysicalMemoryTrait for PhysicalMemory {
     }
 
     fn set_word_by_address(&mut self, address: usize, value: i32) {
-        self[address / PAGE_SIZE].data[address % PAGE_SIZE] = value;
+        let page_size = self.page_size();
+        self[address / page_size].data[address % page_size] = value;
     }
 
     fn set_word_by_offset(&mut self, frame: usize, offset: usize, value: i32) {
@@ -0,0 +1,88 @@
+/// Kind of memory access checked against `Permissions`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Read, write and execute rights of a segment or page.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Permissions(u8);
+
+impl Permissions {
+    pub const NONE: Self = Self(0);
+    pub const READ: Self = Self(1);
+    pub const WRITE: Self = Self(1 << 1);
+    pub const EXECUTE: Self = Self(1 << 2);
+    pub const ALL: Self = Self(Self::READ.0 | Self::WRITE.0 | Self::EXECUTE.0);
+
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    #[must_use]
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    #[must_use]
+    pub const fn allows(self, access: Access) -> bool {
+        let required = match access {
+            Access::Read => Self::READ,
+            Access::Write => Self::WRITE,
+            Access::Execute => Self::EXECUTE,
+        };
+
+        self.0 & required.0 != 0
+    }
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl std::fmt::Display for Permissions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flag = |access, c| if self.allows(access) { c } else { '-' };
+
+        write!(
+            f,
+            "{}{}{}",
+            flag(Access::Read, 'r'),
+            flag(Access::Write, 'w'),
+            flag(Access::Execute, 'x')
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows() {
+        let permissions = Permissions::READ.union(Permissions::EXECUTE);
+
+        assert!(permissions.allows(Access::Read));
+        assert!(!permissions.allows(Access::Write));
+        assert!(permissions.allows(Access::Execute));
+        assert!(!Permissions::NONE.allows(Access::Read));
+    }
+
+    #[test]
+    fn intersection() {
+        let permissions = Permissions::ALL.intersection(Permissions::WRITE);
+        assert_eq!(permissions, Permissions::WRITE);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Permissions::ALL.to_string(), "rwx");
+        assert_eq!(Permissions::READ.to_string(), "r--");
+        assert_eq!(Permissions::NONE.to_string(), "---");
+    }
+}
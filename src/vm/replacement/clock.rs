@@ -0,0 +1,154 @@
+use crate::vm::frame::Frame;
+
+use super::ReplacementPolicy;
+
+/// Second-chance replacement: sweeps the frames in order, clearing
+/// `Frame::referenced` until it finds a frame that has not been used since
+/// the last sweep.
+#[derive(Debug, Default)]
+pub struct Clock {
+    /// Whether each frame is an eviction candidate.
+    tracked: Vec<bool>,
+    hand: usize,
+}
+
+impl Clock {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(&mut self, frame: usize, value: bool) {
+        if frame >= self.tracked.len() {
+            self.tracked.resize(frame + 1, false);
+        }
+        self.tracked[frame] = value;
+    }
+}
+
+impl ReplacementPolicy for Clock {
+    fn name(&self) -> &'static str {
+        "clock"
+    }
+
+    fn loaded(&mut self, frame: usize) {
+        self.set(frame, true);
+    }
+
+    fn accessed(&mut self, _frame: usize) {}
+
+    fn released(&mut self, frame: usize) {
+        self.set(frame, false);
+    }
+
+    fn victim(&mut self, frames: &mut [Frame], pinned: &[usize]) -> Option<usize> {
+        let len = self.tracked.len().min(frames.len()).max(1);
+
+        // Two full sweeps are enough: the first clears every reference bit.
+        for _ in 0..=2 * len {
+            let frame = self.hand % len;
+            self.hand = (frame + 1) % len;
+
+            if !self.tracked.get(frame).copied().unwrap_or(false) || pinned.contains(&frame) {
+                continue;
+            }
+
+            if frames[frame].referenced {
+                frames[frame].referenced = false;
+            } else {
+                self.tracked[frame] = false;
+                return Some(frame);
+            }
+        }
+
+        None
+    }
+
+    /// The hand followed by the tracked frames.
+    fn state(&self) -> Vec<usize> {
+        std::iter::once(self.hand)
+            .chain(
+                self.tracked
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &tracked)| tracked)
+                    .map(|(frame, _)| frame),
+            )
+            .collect()
+    }
+
+    fn restore(&mut self, state: &[usize]) {
+        self.tracked.clear();
+        self.hand = state.first().copied().unwrap_or_default();
+        for &frame in state.iter().skip(1) {
+            self.set(frame, true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frames(referenced: &[usize]) -> Vec<Frame> {
+        (0..5)
+            .map(|i| Frame {
+                referenced: referenced.contains(&i),
+                ..Frame::new(1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn second_chance() {
+        let mut frames = frames(&[2, 3, 4]);
+        let mut clock = Clock::new();
+        clock.loaded(2);
+        clock.loaded(3);
+        clock.loaded(4);
+
+        // All referenced: first sweep clears, second picks the first frame.
+        assert_eq!(clock.victim(&mut frames, &[]), Some(2));
+        assert!(!frames[3].referenced);
+
+        clock.loaded(2);
+        frames[2].referenced = true;
+        frames[3].referenced = true;
+
+        // Frame 3 was referenced again, frame 4 was not.
+        assert_eq!(clock.victim(&mut frames, &[]), Some(4));
+    }
+
+    #[test]
+    fn pinned() {
+        let mut frames = frames(&[]);
+        let mut clock = Clock::new();
+        clock.loaded(2);
+        clock.loaded(3);
+
+        assert_eq!(clock.victim(&mut frames, &[2]), Some(3));
+        assert_eq!(clock.victim(&mut frames, &[2]), None);
+    }
+
+    #[test]
+    fn state() {
+        let mut frames = frames(&[]);
+        let mut clock = Clock::new();
+        clock.loaded(2);
+        clock.loaded(3);
+        clock.loaded(5);
+        assert_eq!(clock.victim(&mut frames, &[]), Some(2));
+
+        let mut restored = Clock::new();
+        restored.restore(&clock.state());
+
+        assert_eq!(restored.state(), vec![3, 3, 5]);
+        assert_eq!(restored.victim(&mut frames, &[]), Some(3));
+    }
+
+    #[test]
+    fn empty() {
+        let mut clock = Clock::new();
+        assert_eq!(clock.victim(&mut [], &[]), None);
+    }
+}
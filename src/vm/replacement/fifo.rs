@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+
+use crate::vm::frame::Frame;
+
+use super::ReplacementPolicy;
+
+/// Evicts the frame that was loaded first.
+#[derive(Debug, Default)]
+pub struct Fifo {
+    queue: VecDeque<usize>,
+}
+
+impl Fifo {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ReplacementPolicy for Fifo {
+    fn name(&self) -> &'static str {
+        "fifo"
+    }
+
+    fn loaded(&mut self, frame: usize) {
+        self.released(frame);
+        self.queue.push_back(frame);
+    }
+
+    fn accessed(&mut self, _frame: usize) {}
+
+    fn released(&mut self, frame: usize) {
+        self.queue.retain(|&f| f != frame);
+    }
+
+    fn victim(&mut self, _frames: &mut [Frame], pinned: &[usize]) -> Option<usize> {
+        let index = self.queue.iter().position(|f| !pinned.contains(f))?;
+        self.queue.remove(index)
+    }
+
+    fn state(&self) -> Vec<usize> {
+        self.queue.iter().copied().collect()
+    }
+
+    fn restore(&mut self, state: &[usize]) {
+        self.queue = state.iter().copied().collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn load_order() {
+        let mut fifo = Fifo::new();
+        fifo.loaded(4);
+        fifo.loaded(2);
+        fifo.loaded(3);
+        fifo.accessed(4);
+
+        assert_eq!(fifo.victim(&mut [], &[]), Some(4));
+        assert_eq!(fifo.victim(&mut [], &[]), Some(2));
+    }
+
+    #[test]
+    fn pinned() {
+        let mut fifo = Fifo::new();
+        fifo.loaded(4);
+        fifo.loaded(2);
+
+        assert_eq!(fifo.victim(&mut [], &[4]), Some(2));
+        assert_eq!(fifo.victim(&mut [], &[4]), None);
+    }
+
+    #[test]
+    fn released() {
+        let mut fifo = Fifo::new();
+        fifo.loaded(4);
+        fifo.loaded(2);
+        fifo.released(4);
+
+        assert_eq!(fifo.victim(&mut [], &[]), Some(2));
+        assert_eq!(fifo.victim(&mut [], &[]), None);
+    }
+}
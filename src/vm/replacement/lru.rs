@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+
+use crate::vm::frame::Frame;
+
+use super::ReplacementPolicy;
+
+/// Evicts the frame that has gone the longest without being accessed.
+#[derive(Debug, Default)]
+pub struct Lru {
+    /// Least recently used frame at the front.
+    order: VecDeque<usize>,
+}
+
+impl Lru {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ReplacementPolicy for Lru {
+    fn name(&self) -> &'static str {
+        "lru"
+    }
+
+    fn loaded(&mut self, frame: usize) {
+        self.accessed(frame);
+    }
+
+    fn accessed(&mut self, frame: usize) {
+        self.released(frame);
+        self.order.push_back(frame);
+    }
+
+    fn released(&mut self, frame: usize) {
+        self.order.retain(|&f| f != frame);
+    }
+
+    fn victim(&mut self, _frames: &mut [Frame], pinned: &[usize]) -> Option<usize> {
+        let index = self.order.iter().position(|f| !pinned.contains(f))?;
+        self.order.remove(index)
+    }
+
+    fn state(&self) -> Vec<usize> {
+        self.order.iter().copied().collect()
+    }
+
+    fn restore(&mut self, state: &[usize]) {
+        self.order = state.iter().copied().collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn least_recently_used() {
+        let mut lru = Lru::new();
+        lru.loaded(4);
+        lru.loaded(2);
+        lru.loaded(3);
+        lru.accessed(4);
+
+        assert_eq!(lru.victim(&mut [], &[]), Some(2));
+        assert_eq!(lru.victim(&mut [], &[]), Some(3));
+        assert_eq!(lru.victim(&mut [], &[]), Some(4));
+    }
+
+    #[test]
+    fn pinned() {
+        let mut lru = Lru::new();
+        lru.loaded(4);
+        lru.loaded(2);
+
+        assert_eq!(lru.victim(&mut [], &[4]), Some(2));
+        assert_eq!(lru.victim(&mut [], &[4]), None);
+    }
+}
@@ -0,0 +1,88 @@
+mod clock;
+mod fifo;
+mod lru;
+
+pub use clock::Clock;
+pub use fifo::Fifo;
+pub use lru::Lru;
+
+use crate::vm::frame::Frame;
+
+/// Chooses which resident frame to evict when physical memory is full.
+///
+/// Only frames reported through `loaded` are eviction candidates, so the
+/// segment table frames are never handed back as victims.
+pub trait ReplacementPolicy: std::fmt::Debug {
+    /// Short name used in reports.
+    fn name(&self) -> &'static str;
+
+    /// Called when `frame` receives a page or page table.
+    fn loaded(&mut self, frame: usize);
+
+    /// Called every time a resident `frame` is used by a translation.
+    fn accessed(&mut self, frame: usize);
+
+    /// Called when `frame` is returned to the free pool without being evicted.
+    fn released(&mut self, frame: usize);
+
+    /// Picks a victim that is not in `pinned` and stops tracking it.
+    ///
+    /// Policies may clear `Frame::referenced` on the frames they pass over.
+    /// Returns `None` if there is no candidate.
+    fn victim(&mut self, frames: &mut [Frame], pinned: &[usize]) -> Option<usize>;
+
+    /// Internal state as a list of numbers, so it can be saved in a snapshot.
+    fn state(&self) -> Vec<usize>;
+
+    /// Replaces the internal state with one returned by `state`.
+    fn restore(&mut self, state: &[usize]);
+}
+
+/// Policies are equal when they are of the same kind and would pick the same
+/// victims.
+impl PartialEq for dyn ReplacementPolicy + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name() && self.state() == other.state()
+    }
+}
+
+impl Eq for dyn ReplacementPolicy + '_ {}
+
+/// Names accepted by `from_name`.
+pub const POLICY_NAMES: [&str; 3] = ["fifo", "lru", "clock"];
+
+/// Creates the policy whose `ReplacementPolicy::name` is `name`.
+#[must_use]
+pub fn from_name(name: &str) -> Option<Box<dyn ReplacementPolicy>> {
+    match name {
+        "fifo" => Some(Box::new(Fifo::new())),
+        "lru" => Some(Box::new(Lru::new())),
+        "clock" => Some(Box::new(Clock::new())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        for name in POLICY_NAMES {
+            let policy = from_name(name).expect("Failed to create policy");
+            assert_eq!(policy.name(), name);
+        }
+
+        assert!(from_name("random").is_none());
+    }
+
+    #[test]
+    fn equality() {
+        let mut fifo = from_name("fifo").expect("Failed to create policy");
+        assert!(fifo == from_name("fifo").expect("Failed to create policy"));
+        assert!(fifo != from_name("lru").expect("Failed to create policy"));
+
+        fifo.loaded(3);
+        assert!(fifo != from_name("fifo").expect("Failed to create policy"));
+    }
+}
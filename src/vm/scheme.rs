@@ -0,0 +1,164 @@
+use std::fmt;
+
+use crate::{
+    error::{VMError, VMResult},
+    geometry::Geometry,
+    vm::inverted::InvertedTable,
+};
+
+/// How `VirtualMemory` translates virtual addresses.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Scheme {
+    /// A segment table per process whose entries bound each segment and point
+    /// to its page table.
+    #[default]
+    Segmented,
+    /// `levels` radix page tables over the flat page number `s` and `p` make
+    /// up together. There are no segment bounds, unmapped pages are simply
+    /// not initialized.
+    Radix { levels: usize },
+    /// A single hashed inverted page table with an entry per frame, shared by
+    /// every process. There are no segment bounds either.
+    Inverted,
+    /// Segment table entries hold a limit and the base address of the
+    /// segment, placed whole in contiguous frames. There are no page tables.
+    PureSegmentation,
+    /// A single page table per process over the flat page number, as long as
+    /// the highest page it maps. There are no segments.
+    PurePaging,
+}
+
+impl Scheme {
+    /// Checks that the scheme's tables fit the frames of `geometry`.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidGeometry` if radix tables have no levels, more
+    ///   levels than page number bits, or more entries than a frame has words,
+    ///   or if an inverted table leaves no frame for pages.
+    pub const fn validate(self, geometry: &Geometry) -> VMResult<()> {
+        match self {
+            Self::Radix { levels } => {
+                let page_number_bits = geometry.segment_bits() + geometry.page_bits();
+                if levels == 0
+                    || levels > page_number_bits
+                    || page_number_bits.div_ceil(levels) > geometry.offset_bits()
+                {
+                    Err(VMError::InvalidGeometry)
+                } else {
+                    Ok(())
+                }
+            }
+            Self::Inverted if InvertedTable::table_frames(geometry) >= geometry.frame_count() => {
+                Err(VMError::InvalidGeometry)
+            }
+            Self::Segmented | Self::Inverted | Self::PureSegmentation | Self::PurePaging => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Segmented => write!(f, "segmented"),
+            Self::Radix { levels } => write!(f, "radix:{levels}"),
+            Self::Inverted => write!(f, "inverted"),
+            Self::PureSegmentation => write!(f, "pure-segmentation"),
+            Self::PurePaging => write!(f, "pure-paging"),
+        }
+    }
+}
+
+/// Parses a scheme as written by its `Display`: `segmented`, `radix:N`,
+/// `inverted`, `pure-segmentation` or `pure-paging`.
+#[must_use]
+pub fn from_name(name: &str) -> Option<Scheme> {
+    match name.split_once(':') {
+        None if name == "segmented" => Some(Scheme::Segmented),
+        None if name == "inverted" => Some(Scheme::Inverted),
+        None if name == "pure-segmentation" => Some(Scheme::PureSegmentation),
+        None if name == "pure-paging" => Some(Scheme::PurePaging),
+        Some(("radix", levels)) => levels.parse().ok().map(|levels| Scheme::Radix { levels }),
+        _ => None,
+    }
+}
+
+/// Flat page number of page `p` of segment `s`, for the schemes without
+/// segments.
+#[must_use]
+pub fn page_number(geometry: &Geometry, s: u16, p: u16) -> usize {
+    usize::from(s) << geometry.page_bits() | usize::from(p)
+}
+
+/// Index into the radix table at `level`, 0 being the root, for page `p` of
+/// segment `s`. Every level takes an equal share of the page number bits,
+/// the root gets whatever is left over.
+#[must_use]
+pub fn radix_index(geometry: &Geometry, levels: usize, s: u16, p: u16, level: usize) -> usize {
+    let page_number = page_number(geometry, s, p);
+    let level_bits = (geometry.segment_bits() + geometry.page_bits()).div_ceil(levels);
+
+    page_number >> (level_bits * (levels - 1 - level)) & ((1 << level_bits) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let geometry = Geometry::default();
+
+        assert_eq!(Scheme::Segmented.validate(&geometry), Ok(()));
+        assert_eq!(Scheme::Radix { levels: 2 }.validate(&geometry), Ok(()));
+        assert_eq!(Scheme::Radix { levels: 18 }.validate(&geometry), Ok(()));
+        assert_eq!(
+            Scheme::Radix { levels: 1 }.validate(&geometry),
+            Err(VMError::InvalidGeometry)
+        );
+        assert_eq!(
+            Scheme::Radix { levels: 0 }.validate(&geometry),
+            Err(VMError::InvalidGeometry)
+        );
+        assert_eq!(
+            Scheme::Radix { levels: 19 }.validate(&geometry),
+            Err(VMError::InvalidGeometry)
+        );
+
+        // 4 words per frame fill all 4 frames of 4 words
+        let geometry = Geometry::new(1, 1, 2, 4).expect("Failed to create Geometry");
+        assert_eq!(
+            Scheme::Inverted.validate(&geometry),
+            Err(VMError::InvalidGeometry)
+        );
+    }
+
+    #[test]
+    fn names() {
+        for scheme in [
+            Scheme::Segmented,
+            Scheme::Radix { levels: 3 },
+            Scheme::Inverted,
+            Scheme::PureSegmentation,
+            Scheme::PurePaging,
+        ] {
+            assert_eq!(from_name(&scheme.to_string()), Some(scheme));
+        }
+        assert_eq!(from_name("radix"), None);
+        assert_eq!(from_name("radix:x"), None);
+        assert_eq!(from_name("segmented:2"), None);
+    }
+
+    #[test]
+    fn radix_index() {
+        let geometry = Geometry::default();
+
+        // 18 page number bits split 6, 6 and 6: 8 << 9 | 5 is 1, 0, 5
+        assert_eq!(super::radix_index(&geometry, 3, 8, 5, 0), 1);
+        assert_eq!(super::radix_index(&geometry, 3, 8, 5, 1), 0);
+        assert_eq!(super::radix_index(&geometry, 3, 8, 5, 2), 5);
+
+        // 18 bits split 2, 4, 4, 4 and 4
+        assert_eq!(super::radix_index(&geometry, 5, 511, 511, 0), 3);
+        assert_eq!(super::radix_index(&geometry, 5, 511, 511, 4), 15);
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+
+/// Counters collected while translating addresses.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Stats {
+    pub translations: u64,
+    /// Page tables and paged segment table pages loaded from disk.
+    pub page_table_faults: u64,
+    /// Pages loaded from disk, or whole segments with pure segmentation.
+    pub page_faults: u64,
+    /// Page tables, pages or segments mapped zeroed with demand zero instead
+    /// of failing with `MemoryNotInitialized`.
+    pub zero_fills: u64,
+    /// Addresses past the end of their segment.
+    pub out_of_bounds: u64,
+    /// Addresses whose page table or page entry is 0.
+    pub not_initialized: u64,
+    pub frames_allocated: u64,
+    pub disk_reads: u64,
+}
+
+impl Stats {
+    /// Translations that failed with `VirtualAddressOutOfBounds` or `MemoryNotInitialized`.
+    #[must_use]
+    pub const fn invalid_accesses(&self) -> u64 {
+        self.out_of_bounds + self.not_initialized
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "translations: {}", self.translations)?;
+        writeln!(f, "page table faults: {}", self.page_table_faults)?;
+        writeln!(f, "page faults: {}", self.page_faults)?;
+        writeln!(f, "zero fills: {}", self.zero_fills)?;
+        writeln!(
+            f,
+            "invalid accesses: {} ({} out of bounds, {} not initialized)",
+            self.invalid_accesses(),
+            self.out_of_bounds,
+            self.not_initialized
+        )?;
+        writeln!(f, "frames allocated: {}", self.frames_allocated)?;
+        write!(f, "disk reads: {}", self.disk_reads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let stats = Stats {
+            translations: 6,
+            page_table_faults: 1,
+            page_faults: 2,
+            zero_fills: 1,
+            out_of_bounds: 1,
+            not_initialized: 2,
+            frames_allocated: 3,
+            disk_reads: 3,
+        };
+
+        assert_eq!(stats.invalid_accesses(), 3);
+        assert_eq!(
+            stats.to_string(),
+            "translations: 6\n\
+             page table faults: 1\n\
+             page faults: 2\n\
+             zero fills: 1\n\
+             invalid accesses: 3 (1 out of bounds, 2 not initialized)\n\
+             frames allocated: 3\n\
+             disk reads: 3"
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{
+    constants::WORD_BITS,
+    error::{VMError, VMResult},
+};
+
+/// How a full TLB set picks the entry to replace.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TlbReplacement {
+    Fifo,
+    Lru,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TlbConfig {
+    pub entries: usize,
+    /// Entries per set. Equal to `entries` for a fully associative TLB.
+    pub associativity: usize,
+    pub replacement: TlbReplacement,
+}
+
+impl TlbConfig {
+    /// Checks that the entries split evenly into sets.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidTlbConfig` if `entries` is not a positive multiple of `associativity`.
+    pub const fn validate(&self) -> VMResult<()> {
+        if self.associativity == 0
+            || self.entries == 0
+            || !self.entries.is_multiple_of(self.associativity)
+        {
+            return Err(VMError::InvalidTlbConfig);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct TlbStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl TlbStats {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TlbEntry {
+    pub s: u16,
+    pub p: u16,
+    pub frame: usize,
+    /// Time the entry was inserted.
+    pub loaded: u64,
+    /// Time the entry was last looked up.
+    pub used: u64,
+}
+
+/// Set-associative cache of `(s, p)` to page frame translations.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Tlb {
+    config: TlbConfig,
+    sets: Vec<Vec<TlbEntry>>,
+    time: u64,
+    stats: TlbStats,
+}
+
+impl Tlb {
+    /// Creates an empty `Tlb`.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidTlbConfig` if `entries` is not a positive multiple of `associativity`.
+    pub fn new(config: TlbConfig) -> VMResult<Self> {
+        config.validate()?;
+
+        Ok(Self {
+            config,
+            sets: vec![
+                Vec::with_capacity(config.associativity);
+                config.entries / config.associativity
+            ],
+            time: 0,
+            stats: TlbStats::default(),
+        })
+    }
+
+    /// Rebuilds a `Tlb` from the parts returned by `config`, `stats`, `time` and `entries`.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidTlbConfig` if `config` is invalid or a set would overflow.
+    pub fn restore(
+        config: TlbConfig,
+        stats: TlbStats,
+        time: u64,
+        entries: Vec<TlbEntry>,
+    ) -> VMResult<Self> {
+        let mut tlb = Self::new(config)?;
+        tlb.stats = stats;
+        tlb.time = time;
+
+        for entry in entries {
+            let index = tlb.set_index(entry.s, entry.p);
+            if tlb.sets[index].len() == config.associativity {
+                return Err(VMError::InvalidTlbConfig);
+            }
+            tlb.sets[index].push(entry);
+        }
+
+        Ok(tlb)
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> TlbConfig {
+        self.config
+    }
+
+    #[must_use]
+    pub const fn stats(&self) -> TlbStats {
+        self.stats
+    }
+
+    /// Logical clock used to order entries for replacement.
+    #[must_use]
+    pub const fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Every cached entry, set by set.
+    pub fn entries(&self) -> impl Iterator<Item = &TlbEntry> {
+        self.sets.iter().flatten()
+    }
+
+    fn set_index(&self, s: u16, p: u16) -> usize {
+        ((usize::from(s) << WORD_BITS) | usize::from(p)) % self.sets.len()
+    }
+
+    /// Returns the frame cached for page `p` of segment `s`, counting a hit or miss.
+    pub fn lookup(&mut self, s: u16, p: u16) -> Option<usize> {
+        self.time += 1;
+        let time = self.time;
+        let index = self.set_index(s, p);
+
+        let frame = self.sets[index]
+            .iter_mut()
+            .find(|entry| entry.s == s && entry.p == p)
+            .map(|entry| {
+                entry.used = time;
+                entry.frame
+            });
+
+        if frame.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+
+        frame
+    }
+
+    /// Caches `frame` for page `p` of segment `s`, replacing an entry if the set is full.
+    pub fn insert(&mut self, s: u16, p: u16, frame: usize) {
+        self.time += 1;
+        let entry = TlbEntry {
+            s,
+            p,
+            frame,
+            loaded: self.time,
+            used: self.time,
+        };
+        let index = self.set_index(s, p);
+        let replacement = self.config.replacement;
+        let set = &mut self.sets[index];
+
+        set.retain(|entry| !(entry.s == s && entry.p == p));
+
+        if set.len() < self.config.associativity {
+            set.push(entry);
+            return;
+        }
+
+        let victim = set
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, entry)| match replacement {
+                TlbReplacement::Fifo => entry.loaded,
+                TlbReplacement::Lru => entry.used,
+            })
+            .map(|(i, _)| i);
+
+        if let Some(victim) = victim {
+            set[victim] = entry;
+        }
+    }
+
+    /// Drops every entry that maps to `frame`.
+    pub fn invalidate_frame(&mut self, frame: usize) {
+        for set in &mut self.sets {
+            set.retain(|entry| entry.frame != frame);
+        }
+    }
+
+    /// Drops every entry.
+    pub fn clear(&mut self) {
+        for set in &mut self.sets {
+            set.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlb(entries: usize, associativity: usize, replacement: TlbReplacement) -> Tlb {
+        Tlb::new(TlbConfig {
+            entries,
+            associativity,
+            replacement,
+        })
+        .expect("Failed to create Tlb")
+    }
+
+    #[test]
+    fn invalid_config() {
+        let config = TlbConfig {
+            entries: 6,
+            associativity: 4,
+            replacement: TlbReplacement::Lru,
+        };
+        assert_eq!(Tlb::new(config), Err(VMError::InvalidTlbConfig));
+
+        let config = TlbConfig {
+            entries: 0,
+            associativity: 0,
+            replacement: TlbReplacement::Lru,
+        };
+        assert_eq!(Tlb::new(config), Err(VMError::InvalidTlbConfig));
+    }
+
+    #[test]
+    fn hit_and_miss() {
+        let mut tlb = tlb(4, 4, TlbReplacement::Lru);
+
+        assert_eq!(tlb.lookup(8, 0), None);
+        tlb.insert(8, 0, 10);
+        assert_eq!(tlb.lookup(8, 0), Some(10));
+
+        assert_eq!(tlb.stats(), TlbStats { hits: 1, misses: 1 });
+        assert!((tlb.stats().hit_ratio() - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn lru() {
+        let mut tlb = tlb(2, 2, TlbReplacement::Lru);
+        tlb.insert(0, 0, 2);
+        tlb.insert(0, 1, 3);
+        tlb.lookup(0, 0);
+        tlb.insert(0, 2, 4);
+
+        assert_eq!(tlb.lookup(0, 0), Some(2));
+        assert_eq!(tlb.lookup(0, 1), None);
+    }
+
+    #[test]
+    fn fifo() {
+        let mut tlb = tlb(2, 2, TlbReplacement::Fifo);
+        tlb.insert(0, 0, 2);
+        tlb.insert(0, 1, 3);
+        tlb.lookup(0, 0);
+        tlb.insert(0, 2, 4);
+
+        assert_eq!(tlb.lookup(0, 0), None);
+        assert_eq!(tlb.lookup(0, 1), Some(3));
+    }
+
+    #[test]
+    fn direct_mapped() {
+        let mut tlb = tlb(2, 1, TlbReplacement::Lru);
+        tlb.insert(0, 0, 2);
+        tlb.insert(0, 1, 3);
+        tlb.insert(0, 2, 4);
+
+        // Pages 0 and 2 share a set
+        assert_eq!(tlb.lookup(0, 0), None);
+        assert_eq!(tlb.lookup(0, 1), Some(3));
+        assert_eq!(tlb.lookup(0, 2), Some(4));
+    }
+
+    #[test]
+    fn invalidate_frame() {
+        let mut tlb = tlb(4, 2, TlbReplacement::Lru);
+        tlb.insert(0, 0, 2);
+        tlb.insert(1, 0, 3);
+        tlb.invalidate_frame(2);
+
+        assert_eq!(tlb.lookup(0, 0), None);
+        assert_eq!(tlb.lookup(1, 0), Some(3));
+    }
+
+    #[test]
+    fn restore() {
+        let mut tlb = tlb(2, 2, TlbReplacement::Lru);
+        tlb.insert(0, 0, 2);
+        tlb.insert(0, 1, 3);
+        tlb.lookup(0, 0);
+
+        let mut restored = Tlb::restore(
+            tlb.config(),
+            tlb.stats(),
+            tlb.time(),
+            tlb.entries().copied().collect(),
+        )
+        .expect("Failed to restore Tlb");
+        assert_eq!(restored, tlb);
+
+        // Replacement order survives: page 1 is still least recently used
+        restored.insert(0, 2, 4);
+        assert_eq!(restored.lookup(0, 1), None);
+
+        // Entries for pages 0 and 1 twice overflow the single set
+        let entries = tlb.entries().chain(tlb.entries()).copied().collect();
+        assert_eq!(
+            Tlb::restore(tlb.config(), tlb.stats(), tlb.time(), entries),
+            Err(VMError::InvalidTlbConfig)
+        );
+    }
+}
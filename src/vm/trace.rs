@@ -0,0 +1,148 @@
+use std::fmt;
+
+use crate::{
+    error::VMResult,
+    vm::{frame::FrameKind, virtual_address::VirtualAddress, virtual_memory::Address},
+};
+
+/// A page table or page copied from disk, or zeroed with demand zero,
+/// during a translation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PageIn {
+    pub kind: FrameKind,
+    /// Disk block it was copied from, `None` if it was zeroed instead.
+    pub block: Option<usize>,
+    pub frame: usize,
+}
+
+impl fmt::Display for PageIn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            FrameKind::SegmentTable => "segment table",
+            FrameKind::SegmentDirectory => "segment directory",
+            FrameKind::PageDirectory => "page directory",
+            FrameKind::InvertedTable => "inverted table",
+            FrameKind::Segment => "segment",
+            FrameKind::PageTable => "page table",
+            FrameKind::Page => "page",
+        };
+
+        match self.block {
+            Some(block) => write!(f, "{kind} in from block {block} to frame {}", self.frame),
+            None => write!(f, "{kind} zeroed in frame {}", self.frame),
+        }
+    }
+}
+
+/// Everything that happened while translating one virtual address.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Trace {
+    pub virtual_address: VirtualAddress,
+    pub segment_size: i32,
+    /// Segment table entry for the page table before the translation: a
+    /// frame, a negative disk block or 0.
+    pub page_table: i32,
+    pub page_ins: Vec<PageIn>,
+    pub result: VMResult<Address>,
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let VirtualAddress { s, p, w, pw } = self.virtual_address;
+        write!(
+            f,
+            "s={s} p={p} w={w} pw={pw} size={} pt={}",
+            self.segment_size, self.page_table
+        )?;
+
+        for page_in in &self.page_ins {
+            write!(f, "; {page_in}")?;
+        }
+
+        match &self.result {
+            Ok(address) => write!(f, "; -> {address}"),
+            Err(error) => write!(f, "; error: {error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::VMError;
+
+    use super::*;
+
+    #[test]
+    fn display() {
+        let virtual_address = VirtualAddress {
+            s: 9,
+            p: 1,
+            w: 10,
+            pw: 522,
+        };
+        let trace = Trace {
+            virtual_address,
+            segment_size: 5000,
+            page_table: -7,
+            page_ins: vec![
+                PageIn {
+                    kind: FrameKind::PageTable,
+                    block: Some(7),
+                    frame: 2,
+                },
+                PageIn {
+                    kind: FrameKind::Page,
+                    block: Some(25),
+                    frame: 4,
+                },
+            ],
+            result: Ok(2058),
+        };
+
+        assert_eq!(
+            trace.to_string(),
+            "s=9 p=1 w=10 pw=522 size=5000 pt=-7; \
+             page table in from block 7 to frame 2; \
+             page in from block 25 to frame 4; -> 2058"
+        );
+
+        let trace = Trace {
+            virtual_address,
+            segment_size: 5000,
+            page_table: 0,
+            page_ins: vec![
+                PageIn {
+                    kind: FrameKind::PageTable,
+                    block: None,
+                    frame: 2,
+                },
+                PageIn {
+                    kind: FrameKind::Page,
+                    block: None,
+                    frame: 4,
+                },
+            ],
+            result: Ok(2058),
+        };
+
+        assert_eq!(
+            trace.to_string(),
+            "s=9 p=1 w=10 pw=522 size=5000 pt=0; \
+             page table zeroed in frame 2; \
+             page zeroed in frame 4; -> 2058"
+        );
+
+        let trace = Trace {
+            virtual_address,
+            segment_size: 0,
+            page_table: 0,
+            page_ins: vec![],
+            result: Err(VMError::VirtualAddressOutOfBounds),
+        };
+
+        assert_eq!(
+            trace.to_string(),
+            "s=9 p=1 w=10 pw=522 size=0 pt=0; error: virtual address is out of bounds"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    constants::{PAGE_SIZE_BITS, SEGMENT_SIZE_BITS},
     error::{VMError, VMResult},
+    geometry::Geometry,
 };
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -12,22 +12,28 @@ pub struct VirtualAddress {
 }
 
 impl VirtualAddress {
-    /// Creates a new `VirtualAddress` from the given `virtual_address`.
+    /// Creates a new `VirtualAddress` from the given `virtual_address`, split
+    /// according to `geometry`.
     ///
     /// # Errors
     /// - `VMError::VirtualAddressLeadingBits` if the leading bits of the `virtual_address` are not 0.
-    pub const fn new(virtual_address: u32) -> VMResult<Self> {
-        let mask = u32::MAX >> (32 - (SEGMENT_SIZE_BITS + PAGE_SIZE_BITS + PAGE_SIZE_BITS));
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn new(virtual_address: u32, geometry: &Geometry) -> VMResult<Self> {
+        let mask = u32::MAX >> (32 - geometry.address_bits());
 
         if (virtual_address & mask) != virtual_address {
             return Err(VMError::VirtualAddressLeadingBits);
         }
 
+        let offset_bits = geometry.offset_bits();
+        let pw_bits = geometry.page_bits() + offset_bits;
+        let pw = virtual_address & (u32::MAX >> (32 - pw_bits));
+
         Ok(Self {
-            s: (virtual_address >> 18) as u16,
-            w: (virtual_address & 0x1FF) as u16,
-            p: ((virtual_address >> 9) & 0x1FF) as u16,
-            pw: virtual_address & 0x3FFFF,
+            s: (virtual_address >> pw_bits) as u16,
+            w: (virtual_address & ((1 << offset_bits) - 1)) as u16,
+            p: (pw >> offset_bits) as u16,
+            pw,
         })
     }
 }
@@ -38,7 +44,7 @@ mod tests {
 
     #[test]
     fn new_virtual_address() {
-        let virtual_address = VirtualAddress::new(0);
+        let virtual_address = VirtualAddress::new(0, &Geometry::default());
         assert_eq!(
             virtual_address,
             Ok(VirtualAddress {
@@ -49,7 +55,7 @@ mod tests {
             })
         );
 
-        let virtual_address = VirtualAddress::new(0x3FFFF);
+        let virtual_address = VirtualAddress::new(0x3FFFF, &Geometry::default());
         assert_eq!(
             virtual_address,
             Ok(VirtualAddress {
@@ -60,7 +66,7 @@ mod tests {
             })
         );
 
-        let virtual_address = VirtualAddress::new(0x7FFFFFF);
+        let virtual_address = VirtualAddress::new(0x07FF_FFFF, &Geometry::default());
         assert_eq!(
             virtual_address,
             Ok(VirtualAddress {
@@ -72,9 +78,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn new_virtual_address_geometry() {
+        let geometry = Geometry::new(10, 10, 12, 64).expect("Failed to create Geometry");
+
+        let virtual_address = VirtualAddress::new(0xFFFF_FFFF, &geometry);
+        assert_eq!(
+            virtual_address,
+            Ok(VirtualAddress {
+                s: 0x3FF,
+                p: 0x3FF,
+                w: 0xFFF,
+                pw: 0x3F_FFFF
+            })
+        );
+
+        let virtual_address = VirtualAddress::new(0x0040_1005, &geometry);
+        assert_eq!(
+            virtual_address,
+            Ok(VirtualAddress {
+                s: 1,
+                p: 1,
+                w: 5,
+                pw: 0x1005
+            })
+        );
+    }
+
     #[test]
     fn new_virtual_address_invalid() {
-        let virtual_address = VirtualAddress::new(0x80000000);
+        let virtual_address = VirtualAddress::new(0x8000_0000, &Geometry::default());
         assert_eq!(virtual_address, Err(VMError::VirtualAddressLeadingBits));
     }
 }
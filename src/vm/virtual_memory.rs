@@ -1,242 +1,3249 @@
+use std::collections::HashMap;
+
 use crate::{
-    constants::{
-        PAGE_COUNT, PAGE_SIZE, SEGMENT_PAGE_TABLE_OFFSET, SEGMENT_SIZE_OFFSET, SEGMENT_WORD_COUNT,
-    },
+    constants::{SEGMENT_PAGE_TABLE_OFFSET, SEGMENT_SIZE_OFFSET, SEGMENT_WORD_COUNT},
     error::{VMError, VMResult},
-    io::{pt_input::PTInput, st_input::STInput},
+    geometry::Geometry,
+    io::{protection_input::ProtectionInput, pt_input::PTInput, st_input::STInput},
     vm::{
+        address_space::AddressSpace,
+        allocator::{self, ContiguousAllocator, Fit, FragmentationReport},
+        disk::Disk,
+        frame::{EntryLocation, Frame, FrameKind},
+        inverted::{InvertedStats, InvertedTable, PageKey},
         physical_memory::{PhysicalMemory, PhysicalMemoryTrait},
+        protection::{Access, Permissions},
+        replacement::{Fifo, ReplacementPolicy},
+        scheme::{page_number, Scheme},
+        stats::Stats,
+        tlb::{Tlb, TlbConfig, TlbStats},
+        trace::{PageIn, Trace},
         virtual_address::VirtualAddress,
     },
 };
 
+mod inverted;
+mod pure_paging;
+mod pure_segmentation;
+mod radix;
+mod snapshot;
+
 pub type Address = u32;
+pub type ProcessId = usize;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug)]
 pub struct VirtualMemory {
+    geometry: Geometry,
     physical_memory: PhysicalMemory,
-    disk: Vec<[i32; PAGE_SIZE]>,
+    disk: Disk,
+    policy: Box<dyn ReplacementPolicy>,
+    address_spaces: Vec<AddressSpace>,
+    active: ProcessId,
+    tlb: Option<Tlb>,
+    stats: Stats,
+    scheme: Scheme,
+    /// State of the inverted page table, with `Scheme::Inverted`.
+    inverted: Option<InvertedTable>,
+    /// Picks the frames segments are loaded into, with
+    /// `Scheme::PureSegmentation`.
+    allocator: ContiguousAllocator,
+    /// Whether touching a 0 entry inside a segment maps a zeroed frame.
+    demand_zero: bool,
+    /// Whether segment tables are paged behind a resident directory.
+    paged_segment_table: bool,
+    /// Page ins of the translation being traced, if any.
+    page_ins: Option<Vec<PageIn>>,
+    /// Pages mapping each frame shared by `fork`, in process order, so every
+    /// entry of a shared frame is found without walking the page tables.
+    sharers: HashMap<usize, Vec<PageKey>>,
+}
+
+// Written out since a derive cannot compare the boxed policy
+impl PartialEq for VirtualMemory {
+    fn eq(&self, other: &Self) -> bool {
+        self.geometry == other.geometry
+            && self.physical_memory == other.physical_memory
+            && self.disk == other.disk
+            && *self.policy == *other.policy
+            && self.address_spaces == other.address_spaces
+            && self.active == other.active
+            && self.tlb == other.tlb
+            && self.stats == other.stats
+            && self.scheme == other.scheme
+            && self.inverted == other.inverted
+            && self.allocator == other.allocator
+            && self.demand_zero == other.demand_zero
+            && self.paged_segment_table == other.paged_segment_table
+            && self.page_ins == other.page_ins
+            && self.sharers == other.sharers
+    }
 }
 
+impl Eq for VirtualMemory {}
+
 impl VirtualMemory {
+    /// Creates a new `VirtualMemory` with the default `Geometry` and a single
+    /// process whose segment table lives in the first frames. Frames are
+    /// evicted in FIFO order.
     pub fn new(segment_table_init: Vec<STInput>, page_table_init: Vec<PTInput>) -> VMResult<Self> {
-        let mut physical_memory = PhysicalMemory::new_memory();
-        let mut disk = vec![[0; PAGE_SIZE]; PAGE_COUNT];
+        Self::with_geometry(Geometry::default(), segment_table_init, page_table_init)
+    }
+
+    /// Creates a new `VirtualMemory` laid out according to `geometry`.
+    ///
+    /// The inputs must have been parsed with the same `geometry`.
+    pub fn with_geometry(
+        geometry: Geometry,
+        segment_table_init: Vec<STInput>,
+        page_table_init: Vec<PTInput>,
+    ) -> VMResult<Self> {
+        Self::with_scheme(
+            geometry,
+            Scheme::Segmented,
+            segment_table_init,
+            page_table_init,
+        )
+    }
+
+    /// Creates a new `VirtualMemory` that translates addresses with `scheme`.
+    ///
+    /// Radix page tables are built from the page table inputs alone, in
+    /// frames allocated around the pages they map, and so are an inverted
+    /// page table and a pure paging page table, which take the first frames.
+    /// Segment sizes and page table frames are ignored. Pure segmentation
+    /// places each segment in the frames or blocks starting at its page table
+    /// frame instead, and ignores the page table inputs.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidGeometry` if the scheme's tables do not fit `geometry`.
+    /// - `VMError::MemoryFull` if a pure paging page table does not fit.
+    pub fn with_scheme(
+        geometry: Geometry,
+        scheme: Scheme,
+        segment_table_init: Vec<STInput>,
+        page_table_init: Vec<PTInput>,
+    ) -> VMResult<Self> {
+        scheme.validate(&geometry)?;
+
+        let mut virtual_memory = Self {
+            geometry,
+            physical_memory: PhysicalMemory::new_memory(&geometry),
+            disk: Disk::new(&geometry),
+            policy: Box::new(Fifo::new()),
+            address_spaces: Vec::new(),
+            active: 0,
+            tlb: None,
+            stats: Stats::default(),
+            scheme,
+            inverted: None,
+            allocator: ContiguousAllocator::default(),
+            demand_zero: false,
+            paged_segment_table: false,
+            page_ins: None,
+            sharers: HashMap::new(),
+        };
 
         // Mark Segment Table Frames as taken
-        physical_memory
-            .iter_mut()
-            .take(SEGMENT_WORD_COUNT)
-            .for_each(|frame| frame.free = false);
+        let (count, kind) = virtual_memory.segment_table_layout(&page_table_init);
+        if count >= geometry.frame_count() {
+            return Err(VMError::MemoryFull);
+        }
+        for frame in &mut virtual_memory.physical_memory[..count] {
+            frame.refs = 1;
+            frame.kind = kind;
+        }
+        virtual_memory
+            .address_spaces
+            .push(AddressSpace::new((0..count).collect(), &geometry));
 
-        for st_input in segment_table_init {
-            let segment_base_address = usize::from(st_input.segment) * SEGMENT_WORD_COUNT;
+        if scheme == Scheme::Inverted {
+            let table_frames = InvertedTable::table_frames(&geometry);
+            for frame in &mut virtual_memory.physical_memory[..table_frames] {
+                frame.refs = 1;
+                frame.kind = FrameKind::InvertedTable;
+            }
+            virtual_memory.inverted = Some(InvertedTable::new(&geometry));
+        }
 
-            physical_memory.set_word_by_address(
-                segment_base_address + SEGMENT_SIZE_OFFSET,
-                i32::try_from(st_input.size)?,
-            );
-            physical_memory.set_word_by_address(
-                segment_base_address + SEGMENT_PAGE_TABLE_OFFSET,
-                i32::from(st_input.frame),
-            );
+        virtual_memory.load_tables(0, segment_table_init, page_table_init)?;
+        virtual_memory.register_resident_frames();
+
+        Ok(virtual_memory)
+    }
+
+    /// Fills the segment table of `process` and the page tables it points to,
+    /// returning the page table and page frames that became resident.
+    ///
+    /// # Errors
+    /// - `VMError::SegmentNotFound` if a page belongs to a segment without a
+    ///   page table.
+    fn load_tables(
+        &mut self,
+        process: ProcessId,
+        segment_table_init: Vec<STInput>,
+        page_table_init: Vec<PTInput>,
+    ) -> VMResult<Vec<usize>> {
+        match self.scheme {
+            Scheme::Segmented => {}
+            Scheme::Radix { levels } => {
+                return self.load_radix_tables(process, levels, page_table_init)
+            }
+            Scheme::Inverted => return self.load_inverted_table(process, page_table_init),
+            Scheme::PureSegmentation => return self.load_segments(process, segment_table_init),
+            Scheme::PurePaging => return self.load_page_table(process, page_table_init),
+        }
+
+        let mut resident = Vec::new();
+
+        for st_input in segment_table_init {
+            let size_entry =
+                self.allocate_segment_entry(process, st_input.segment, SEGMENT_SIZE_OFFSET)?;
+            self.set_entry(size_entry, i32::try_from(st_input.size)?);
+            let page_table_entry =
+                self.allocate_segment_entry(process, st_input.segment, SEGMENT_PAGE_TABLE_OFFSET)?;
+            self.set_entry(page_table_entry, i32::from(st_input.frame));
 
             // Mark Page Table Frames as taken
             if st_input.frame.is_positive() {
-                physical_memory[usize::try_from(st_input.frame)?].free = false;
+                let frame_number = usize::try_from(st_input.frame)?;
+                let frame = &mut self.physical_memory[frame_number];
+                frame.refs = 1;
+                frame.kind = FrameKind::PageTable;
+                frame.owner = Some(page_table_entry);
+                resident.push(frame_number);
+            } else {
+                self.disk.claim(usize::try_from(st_input.frame.abs())?);
             }
         }
 
         for pt_input in page_table_init {
-            let pt_frame_num = physical_memory.get_word_by_address(
-                usize::from(pt_input.segment) * SEGMENT_WORD_COUNT + SEGMENT_PAGE_TABLE_OFFSET,
-            );
+            let pt_frame_num =
+                self.segment_word(process, pt_input.segment, SEGMENT_PAGE_TABLE_OFFSET)?;
+            // A segment without a page table would have its pages written
+            // over frame 0
+            if pt_frame_num == 0 {
+                return Err(VMError::SegmentNotFound);
+            }
 
             let page_offset = usize::from(pt_input.page);
 
-            if pt_frame_num.is_negative() {
-                disk[usize::try_from(pt_frame_num.abs())?][page_offset] = i32::from(pt_input.frame);
+            let owner = if pt_frame_num.is_negative() {
+                let block = usize::try_from(pt_frame_num.abs())?;
+                self.disk[block][page_offset] = i32::from(pt_input.frame);
+                EntryLocation::Disk {
+                    block,
+                    offset: page_offset,
+                }
             } else {
-                physical_memory.set_word_by_offset(
-                    usize::try_from(pt_frame_num)?,
+                let pt_frame = usize::try_from(pt_frame_num)?;
+                self.physical_memory.set_word_by_offset(
+                    pt_frame,
                     page_offset,
                     i32::from(pt_input.frame),
                 );
-            }
+                EntryLocation::Memory(pt_frame * self.geometry.page_size() + page_offset)
+            };
 
             // Mark Page Frames as taken
             if pt_input.frame.is_positive() {
-                physical_memory[usize::try_from(pt_input.frame)?].free = false;
+                let frame_number = usize::try_from(pt_input.frame)?;
+                let frame = &mut self.physical_memory[frame_number];
+                frame.refs = 1;
+                frame.kind = FrameKind::Page;
+                frame.owner = Some(owner);
+                resident.push(frame_number);
+            } else {
+                self.disk.claim(usize::try_from(pt_input.frame.abs())?);
             }
         }
 
-        Ok(Self {
-            physical_memory,
-            disk,
-        })
+        Ok(resident)
     }
 
-    fn allocate_page(&mut self) -> VMResult<usize> {
-        self.physical_memory
-            .iter_mut()
-            .enumerate()
-            .skip(SEGMENT_WORD_COUNT)
-            .find(|(_, frame)| frame.free)
-            .map(|(i, frame)| {
-                frame.free = false;
-                i
-            })
-            .ok_or(VMError::MemoryFull)
+    /// Adds a process with its own segment table, allocated from free frames.
+    ///
+    /// The new process is not made active.
+    pub fn create_process(
+        &mut self,
+        segment_table_init: Vec<STInput>,
+        page_table_init: Vec<PTInput>,
+    ) -> VMResult<ProcessId> {
+        // Keep the segment table out of frames the init is about to claim,
+        // only segmented page tables use the page table frames and pure
+        // segmentation has no pages
+        let (page_tables, pages) = match self.scheme {
+            Scheme::Segmented => (segment_table_init.as_slice(), page_table_init.as_slice()),
+            Scheme::Radix { .. } | Scheme::Inverted | Scheme::PurePaging => {
+                (&[][..], page_table_init.as_slice())
+            }
+            Scheme::PureSegmentation => (&[][..], &[][..]),
+        };
+        let mut claimed: Vec<usize> = page_tables
+            .iter()
+            .map(|st_input| st_input.frame)
+            .chain(pages.iter().map(|pt_input| pt_input.frame))
+            .filter(|frame| frame.is_positive())
+            .map(usize::try_from)
+            .collect::<Result<_, _>>()?;
+        if self.scheme == Scheme::PureSegmentation {
+            for &st_input in &segment_table_init {
+                if st_input.frame.is_positive() {
+                    claimed.extend(self.segment_frames(st_input)?);
+                }
+            }
+        }
+        // Frames taken already are left to the loaders to reject
+        claimed.retain(|&frame| self.physical_memory[frame].is_free());
+        claimed.sort_unstable();
+        claimed.dedup();
+        for &frame in &claimed {
+            self.physical_memory[frame].refs = 1;
+        }
+
+        let segment_table = self.allocate_segment_table(&page_table_init);
+        // The loaders claim the frames again, checking them as they go
+        for &frame in &claimed {
+            self.physical_memory[frame].refs = 0;
+        }
+        let segment_table = segment_table?;
+        let process = self.address_spaces.len();
+        self.address_spaces
+            .push(AddressSpace::new(segment_table, &self.geometry));
+
+        let mut resident = self.load_tables(process, segment_table_init, page_table_init)?;
+        resident.sort_unstable();
+        for frame in resident {
+            self.policy.loaded(frame);
+        }
+
+        Ok(process)
     }
 
-    fn get_frame(&mut self, address: usize) -> VMResult<usize> {
-        match self.physical_memory.get_word_by_address(address) {
-            // Not Resident in Memory
-            frame if frame < 0 => {
-                let disk_frame = usize::try_from(frame.abs())?;
-                let free_frame = self.allocate_page()?;
+    /// Creates a child of the active process whose segment and page tables
+    /// are copies of the parent's, with the same protections.
+    ///
+    /// Resident pages are shared read-only until either process writes to
+    /// them with `write_word`, which gives the writer its own copy. Pages on
+    /// disk are copied to new blocks right away, and so is a shared page when
+    /// it is evicted, one block per process. The child is not made active.
+    ///
+    /// # Errors
+    /// - `VMError::MemoryFull` if no frame is left for the child's tables.
+    /// - `VMError::DiskFull` if no block is left for a page on disk.
+    /// - `VMError::UnsupportedScheme` without segment tables.
+    pub fn fork(&mut self) -> VMResult<ProcessId> {
+        self.check_segmented()?;
+        let parent = self.active;
+        let segment_table = self.allocate_segment_table(&[])?;
+        let child = self.address_spaces.len();
+        self.address_spaces.push(AddressSpace {
+            segment_table,
+            ..self.address_spaces[parent].clone()
+        });
 
-                self.physical_memory
-                    .set_word_by_address(address, i32::try_from(free_frame)?);
+        for s in 0..self.geometry.segment_count() {
+            self.fork_segment(parent, child, u16::try_from(s)?)?;
+        }
 
-                // Copy Frame From Disk to Memory
-                for (i, &word) in self.disk[disk_frame].iter().enumerate() {
-                    self.physical_memory.set_word_by_offset(free_frame, i, word);
+        Ok(child)
+    }
+
+    /// Copies segment `s`'s entry and page table from `parent` to `child`,
+    /// adding a reference to every resident page it maps.
+    fn fork_segment(&mut self, parent: ProcessId, child: ProcessId, s: u16) -> VMResult<()> {
+        if self.segment_word(parent, s, SEGMENT_PAGE_TABLE_OFFSET)? == 0 {
+            return Ok(());
+        }
+
+        // Allocate the child's entry first, a paged segment table may need a
+        // page for it and evict part of the parent's tables to get one
+        let size_entry = self.allocate_segment_entry(child, s, SEGMENT_SIZE_OFFSET)?;
+        self.set_entry(
+            size_entry,
+            self.segment_word(parent, s, SEGMENT_SIZE_OFFSET)?,
+        );
+        self.allocate_segment_entry(child, s, SEGMENT_PAGE_TABLE_OFFSET)?;
+
+        let page_table = match self.segment_word(parent, s, SEGMENT_PAGE_TABLE_OFFSET)? {
+            block if block < 0 => -i32::try_from(self.copy_block(usize::try_from(block.abs())?)?)?,
+            frame => {
+                let frame = usize::try_from(frame)?;
+                let copy = self.allocate_page(&[frame])?;
+                self.physical_memory[copy] = Frame {
+                    refs: 1,
+                    kind: FrameKind::PageTable,
+                    owner: None,
+                    referenced: true,
+                    dirty: true,
+                    block: None,
+                    data: self.physical_memory[frame].data.clone(),
+                };
+                self.policy.loaded(copy);
+                i32::try_from(copy)?
+            }
+        };
+
+        // The allocation may have moved the child's segment table page to disk
+        let page_table_entry = self.allocate_segment_entry(child, s, SEGMENT_PAGE_TABLE_OFFSET)?;
+        self.set_entry(page_table_entry, page_table);
+        if let Ok(copy) = usize::try_from(page_table) {
+            self.physical_memory[copy].owner = Some(page_table_entry);
+        }
+
+        for page in 0..self.geometry.pages_per_segment() {
+            let entry = self.entry_location(page_table, page)?;
+            match self.get_entry(entry) {
+                block if block < 0 => {
+                    let copy = self.copy_block(usize::try_from(block.abs())?)?;
+                    self.set_entry(entry, -i32::try_from(copy)?);
+                }
+                0 => {}
+                frame => {
+                    let frame = usize::try_from(frame)?;
+                    let p = u16::try_from(page)?;
+                    self.physical_memory[frame].refs += 1;
+                    self.sharers
+                        .entry(frame)
+                        .or_insert_with(|| vec![(parent, s, p)])
+                        .push((child, s, p));
                 }
+            }
+        }
 
-                Ok(free_frame)
+        Ok(())
+    }
+
+    /// Copies disk block `block` into a newly allocated one.
+    fn copy_block(&mut self, block: usize) -> VMResult<usize> {
+        let copy = self.disk.allocate()?;
+        let data = self.disk[block].to_vec();
+        self.disk[copy].copy_from_slice(&data);
+
+        Ok(copy)
+    }
+
+    /// Makes `process` the active process. The TLB is flushed since its
+    /// entries are not tagged with a process.
+    pub fn switch_to(&mut self, process: ProcessId) -> VMResult<()> {
+        if process >= self.address_spaces.len() {
+            return Err(VMError::InvalidProcess);
+        }
+
+        if process != self.active {
+            self.active = process;
+            if let Some(tlb) = &mut self.tlb {
+                tlb.clear();
             }
-            // Not Initialized
-            0 => Err(VMError::MemoryNotInitialized),
+        }
 
-            // Resident in Memory
-            offset => Ok(usize::try_from(offset)?),
+        Ok(())
+    }
+
+    #[must_use]
+    pub const fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+
+    #[must_use]
+    pub const fn active_process(&self) -> ProcessId {
+        self.active
+    }
+
+    #[must_use]
+    pub const fn process_count(&self) -> usize {
+        self.address_spaces.len()
+    }
+
+    /// Replaces the page replacement policy consulted when memory is full.
+    ///
+    /// Frames that are already resident are handed to the new policy in frame order.
+    #[must_use]
+    pub fn with_policy(mut self, policy: Box<dyn ReplacementPolicy>) -> Self {
+        self.policy = policy;
+        self.register_resident_frames();
+        self
+    }
+
+    /// Puts a TLB in front of the segment and page table walk.
+    pub fn with_tlb(mut self, config: TlbConfig) -> VMResult<Self> {
+        self.tlb = Some(Tlb::new(config)?);
+        Ok(self)
+    }
+
+    /// Maps a fresh zeroed page table or page when a translation inside a
+    /// segment reaches a 0 entry, instead of failing with
+    /// `VMError::MemoryNotInitialized`.
+    #[must_use]
+    pub const fn with_demand_zero(mut self, demand_zero: bool) -> Self {
+        self.demand_zero = demand_zero;
+        self
+    }
+
+    #[must_use]
+    pub const fn demand_zero(&self) -> bool {
+        self.demand_zero
+    }
+
+    /// Picks the hole a segment is loaded into with `fit`, with pure
+    /// segmentation.
+    #[must_use]
+    pub const fn with_fit(mut self, fit: Fit) -> Self {
+        self.allocator = ContiguousAllocator::new(fit);
+        self
+    }
+
+    #[must_use]
+    pub const fn fit(&self) -> Fit {
+        self.allocator.fit()
+    }
+
+    /// How the free frames are split into holes.
+    #[must_use]
+    pub fn fragmentation(&self) -> FragmentationReport {
+        allocator::fragmentation(&self.physical_memory)
+    }
+
+    /// Pages the segment table of every process behind a one frame
+    /// directory. Only the directory stays resident: its entries point at
+    /// segment table pages, which are allocated when a segment first needs
+    /// them and evicted and faulted back in like page tables.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidGeometry` if a segment table has more pages than a
+    ///   directory has entries.
+    /// - `VMError::MemoryFull` if no frame is left for a directory.
+    /// - `VMError::UnsupportedScheme` without segment tables.
+    pub fn with_paged_segment_table(mut self) -> VMResult<Self> {
+        self.check_segmented()?;
+        if self.paged_segment_table {
+            return Ok(self);
+        }
+        if self.geometry.segment_table_frames() > self.geometry.page_size() {
+            return Err(VMError::InvalidGeometry);
+        }
+
+        self.paged_segment_table = true;
+        for process in 0..self.address_spaces.len() {
+            let mut pages = self.address_spaces[process].segment_table.clone();
+            let directory = if pages[0] == 0 {
+                // A 0 directory entry means not initialized, so the first
+                // page moves out and the directory takes frame 0
+                let page = self.allocate_page(&pages)?;
+                self.physical_memory[page] = self.physical_memory[0].clone();
+                let page_size = self.geometry.page_size();
+                self.move_children(page, |offset| {
+                    EntryLocation::Memory(page * page_size + offset)
+                });
+                pages[0] = page;
+                0
+            } else {
+                self.allocate_page(&pages)?
+            };
+
+            self.physical_memory[directory] = Frame {
+                refs: 1,
+                kind: FrameKind::SegmentDirectory,
+                ..Frame::new(self.geometry.page_size())
+            };
+            for (i, &page) in pages.iter().enumerate() {
+                self.physical_memory
+                    .set_word_by_offset(directory, i, i32::try_from(page)?);
+                self.physical_memory[page].owner = Some(EntryLocation::Memory(
+                    directory * self.geometry.page_size() + i,
+                ));
+                self.policy.loaded(page);
+            }
+            self.address_spaces[process].segment_table = vec![directory];
         }
+
+        Ok(self)
     }
 
-    pub fn translate(&mut self, virtual_address: VirtualAddress) -> VMResult<Address> {
-        let segment_base: usize = usize::from(virtual_address.s) * SEGMENT_WORD_COUNT;
-        let segment_size = self
-            .physical_memory
-            .get_word_by_address(segment_base + SEGMENT_SIZE_OFFSET);
+    #[must_use]
+    pub const fn paged_segment_table(&self) -> bool {
+        self.paged_segment_table
+    }
 
-        if virtual_address.pw >= u32::try_from(segment_size)? {
-            return Err(VMError::VirtualAddressOutOfBounds);
+    #[must_use]
+    pub const fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// Fails unless addresses are translated through segment tables and
+    /// the page tables they point to.
+    const fn check_segmented(&self) -> VMResult<()> {
+        match self.scheme {
+            Scheme::Segmented => Ok(()),
+            Scheme::Radix { .. }
+            | Scheme::Inverted
+            | Scheme::PureSegmentation
+            | Scheme::PurePaging => Err(VMError::UnsupportedScheme),
         }
+    }
 
-        let page_table_frame = self.get_frame(segment_base + SEGMENT_PAGE_TABLE_OFFSET)?;
-        let page_frame =
-            self.get_frame(page_table_frame * PAGE_SIZE + usize::from(virtual_address.p))?;
+    /// Fails unless processes have segment tables, with or without pages.
+    const fn check_segment_table(&self) -> VMResult<()> {
+        match self.scheme {
+            Scheme::Segmented | Scheme::PureSegmentation => Ok(()),
+            Scheme::Radix { .. } | Scheme::Inverted | Scheme::PurePaging => {
+                Err(VMError::UnsupportedScheme)
+            }
+        }
+    }
 
-        Ok(u32::try_from(
-            page_frame * PAGE_SIZE + usize::from(virtual_address.w),
-        )?)
+    /// Size of the inverted page table and how long its chains are, with
+    /// `Scheme::Inverted`.
+    #[must_use]
+    pub fn inverted_stats(&self) -> Option<InvertedStats> {
+        self.inverted.as_ref().map(InvertedTable::stats)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Hit and miss counts of the TLB, if one is configured.
+    #[must_use]
+    pub fn tlb_stats(&self) -> Option<TlbStats> {
+        self.tlb.as_ref().map(Tlb::stats)
+    }
 
-    fn before() -> VirtualMemory {
-        let st_inputs = vec![
-            STInput::new("8", "4000", "3").expect("Failed to create PTInput"),
-            STInput::new("9", "5000", "-7").expect("Failed to create PTInput"),
-        ];
-        let pt_inputs = vec![
-            PTInput::new("8", "0", "10").expect("Failed to create PTInput"),
-            PTInput::new("8", "1", "-20").expect("Failed to create PTInput"),
-            PTInput::new("9", "0", "13").expect("Failed to create PTInput"),
-            PTInput::new("9", "1", "-25").expect("Failed to create PTInput"),
-        ];
+    /// Applies segment and page protections to the active process.
+    /// Anything not listed stays `rwx`.
+    #[must_use]
+    pub fn with_protection(mut self, protection_init: Vec<ProtectionInput>) -> Self {
+        self.protect(protection_init);
+        self
+    }
 
-        VirtualMemory::new(st_inputs, pt_inputs).expect("Failed to init")
+    /// Applies segment and page protections to the active process.
+    pub fn protect(&mut self, protection_init: Vec<ProtectionInput>) {
+        self.address_spaces[self.active].protect(protection_init);
     }
 
-    #[test]
-    fn init() {
-        let virtual_memory = before();
+    /// Effective permissions of page `p` in segment `s` of the active process.
+    #[must_use]
+    pub fn permissions(&self, s: u16, p: u16) -> Permissions {
+        self.address_spaces[self.active].permissions(s, p)
+    }
 
-        assert_eq!(virtual_memory.physical_memory[0].data[8 * 2], 4000);
-        assert_eq!(virtual_memory.physical_memory[0].data[8 * 2 + 1], 3);
-        assert_eq!(virtual_memory.physical_memory[0].data[9 * 2], 5000);
-        assert_eq!(virtual_memory.physical_memory[0].data[9 * 2 + 1], -7);
+    /// Fault and allocation counters since the `VirtualMemory` was created.
+    #[must_use]
+    pub const fn stats(&self) -> Stats {
+        self.stats
+    }
 
-        assert_eq!(virtual_memory.disk[7][0], 13);
-        assert_eq!(virtual_memory.disk[7][1], -25);
+    #[must_use]
+    pub fn policy_name(&self) -> &'static str {
+        self.policy.name()
+    }
 
-        assert_eq!(virtual_memory.physical_memory[3].data[0], 10);
-        assert_eq!(virtual_memory.physical_memory[3].data[1], -20);
+    /// Whether frames holding `kind` never leave memory: segment tables
+    /// unless they are paged, the directories of paged segment tables and
+    /// radix page tables, pure paging page tables, the inverted page table
+    /// and pure segmentation segments.
+    const fn is_pinned(&self, kind: FrameKind) -> bool {
+        match kind {
+            FrameKind::SegmentTable => !self.paged_segment_table,
+            FrameKind::SegmentDirectory
+            | FrameKind::PageDirectory
+            | FrameKind::InvertedTable
+            | FrameKind::Segment => true,
+            FrameKind::PageTable | FrameKind::Page => false,
+        }
     }
 
-    #[test]
-    fn simple_translate() {
-        let mut vm = before();
-        let virtual_address =
-            VirtualAddress::new(2097162).expect("Failed to create VirtualAddress");
-        let expected_address = 5130;
+    fn register_resident_frames(&mut self) {
+        for (i, frame) in self.physical_memory.iter().enumerate() {
+            if !frame.is_free() && !self.is_pinned(frame.kind) {
+                self.policy.loaded(i);
+            }
+        }
+    }
 
-        let address = vm.translate(virtual_address).expect("Failed to translate");
+    fn allocate_page(&mut self, pinned: &[usize]) -> VMResult<usize> {
+        let free_frame = self
+            .physical_memory
+            .iter_mut()
+            .enumerate()
+            // Frame 0 can never be referenced since a 0 entry means not initialized
+            .skip(1)
+            .find(|(_, frame)| frame.is_free())
+            .map(|(i, frame)| {
+                frame.refs = 1;
+                i
+            });
 
-        assert_eq!(address, expected_address);
+        if let Some(frame) = free_frame {
+            self.stats.frames_allocated += 1;
+            return Ok(frame);
+        }
+
+        // Kept to undo picking a victim that cannot be evicted
+        let state = self.policy.state();
+        let referenced: Vec<bool> = self
+            .physical_memory
+            .iter()
+            .map(|frame| frame.referenced)
+            .collect();
+
+        let victim = self
+            .policy
+            .victim(&mut self.physical_memory, pinned)
+            .ok_or(VMError::MemoryFull)?;
+        if let Err(error) = self.evict(victim) {
+            // The victim is still resident, so it keeps its place among the
+            // candidates and the frames passed over keep their reference bits
+            self.policy.restore(&state);
+            for (frame, referenced) in self.physical_memory.iter_mut().zip(referenced) {
+                frame.referenced = referenced;
+            }
+            return Err(error);
+        }
+        self.physical_memory[victim].refs = 1;
+        self.stats.frames_allocated += 1;
+
+        Ok(victim)
     }
 
-    #[test]
-    fn pg_not_resident() {
-        let mut vm = before();
-        let virtual_address =
-            VirtualAddress::new(2097674).expect("Failed to create VirtualAddress");
-        let expected_address = 1034;
+    /// Number and kind of the frames a process's segment table takes: the
+    /// whole table, the directory of a paged one, the root of radix tables,
+    /// none with an inverted table or, with pure paging, a page table long
+    /// enough for the pages of `page_table_init`.
+    fn segment_table_layout(&self, page_table_init: &[PTInput]) -> (usize, FrameKind) {
+        match self.scheme {
+            Scheme::Inverted => (0, FrameKind::InvertedTable),
+            Scheme::Radix { .. } => (1, FrameKind::PageDirectory),
+            Scheme::PurePaging => (
+                self.page_table_frames(page_table_init),
+                FrameKind::PageDirectory,
+            ),
+            Scheme::Segmented if self.paged_segment_table => (1, FrameKind::SegmentDirectory),
+            Scheme::Segmented | Scheme::PureSegmentation => (
+                self.geometry.segment_table_frames(),
+                FrameKind::SegmentTable,
+            ),
+        }
+    }
 
-        let address = vm.translate(virtual_address).expect("Failed to translate");
+    /// Allocates the frames for a new process's segment table, or just its
+    /// directory if segment tables are paged or radix tables are used, or
+    /// its page table with pure paging.
+    fn allocate_segment_table(&mut self, page_table_init: &[PTInput]) -> VMResult<Vec<usize>> {
+        let (count, kind) = self.segment_table_layout(page_table_init);
 
-        assert_eq!(address, expected_address);
+        let mut segment_table = Vec::with_capacity(count);
+        for _ in 0..count {
+            let frame = self.allocate_page(&[])?;
+            self.physical_memory[frame] = Frame {
+                refs: 1,
+                kind,
+                ..Frame::new(self.geometry.page_size())
+            };
+            segment_table.push(frame);
+        }
+
+        Ok(segment_table)
     }
 
-    #[test]
-    fn pt_not_resident() {
-        let mut vm = before();
-        let virtual_address =
-            VirtualAddress::new(2359306).expect("Failed to create VirtualAddress");
-        let expected_address = 6666;
+    fn set_entry(&mut self, entry: EntryLocation, value: i32) {
+        match entry {
+            EntryLocation::Memory(address) => self.set_word(address, value),
+            EntryLocation::Disk { block, offset } => self.disk[block][offset] = value,
+        }
+    }
 
-        let address = vm.translate(virtual_address).expect("Failed to translate");
+    /// Points the owners of the frames mapped by table `frame`, a page table
+    /// or segment table page, at `location`.
+    fn move_children(&mut self, frame: usize, location: impl Fn(usize) -> EntryLocation) {
+        // Segment table entries alternate sizes and page tables
+        let (first, step) = match self.physical_memory[frame].kind {
+            FrameKind::SegmentTable => (SEGMENT_PAGE_TABLE_OFFSET, SEGMENT_WORD_COUNT),
+            _ => (0, 1),
+        };
 
-        assert_eq!(address, expected_address);
+        for offset in (first..self.geometry.page_size()).step_by(step) {
+            let child = self.physical_memory.get_word_by_offset(frame, offset);
+            if let Ok(child) = usize::try_from(child) {
+                if child > 0 {
+                    self.physical_memory[child].owner = Some(location(offset));
+                }
+            }
+        }
     }
 
-    #[test]
-    fn pt_and_pg_not_resident() {
-        let mut vm = before();
-        let virtual_address =
-            VirtualAddress::new(2359818).expect("Failed to create VirtualAddress");
-        let expected_address = 2058;
+    /// Sets the word at `address` and marks its frame as modified.
+    fn set_word(&mut self, address: usize, value: i32) {
+        self.physical_memory.set_word_by_address(address, value);
+        self.physical_memory[address / self.geometry.page_size()].dirty = true;
+    }
 
-        let address = vm.translate(virtual_address).expect("Failed to translate");
+    /// Copies `frame` to its disk block, allocating one if it has none yet.
+    fn write_back(&mut self, frame: usize) -> VMResult<usize> {
+        let block = match self.physical_memory[frame].block {
+            Some(block) => block,
+            None => self.disk.allocate()?,
+        };
 
-        assert_eq!(address, expected_address);
+        self.disk[block].copy_from_slice(&self.physical_memory[frame].data);
+        self.physical_memory[frame].block = Some(block);
+        self.physical_memory[frame].dirty = false;
+
+        Ok(block)
     }
 
-    #[test]
-    fn find_free_page() {
-        let mut vm = VirtualMemory::new(vec![], vec![]).expect("Failed to init");
+    /// Moves `frame` to disk and points its owning entry there.
+    ///
+    /// Clean frames that already have a disk block are not written.
+    fn evict(&mut self, frame: usize) -> VMResult<()> {
+        let block = match self.physical_memory[frame].block {
+            Some(block) if !self.physical_memory[frame].dirty => block,
+            _ => self.write_back(frame)?,
+        };
 
-        let free_page = vm.allocate_page().expect("Failed to find free page");
-        assert_eq!(free_page, 2);
+        // Done first since it is the only step that can fail after the write
+        if self.physical_memory[frame].is_shared() {
+            self.evict_shared(frame, block)?;
+        }
 
-        let free_page = vm.allocate_page().expect("Failed to find free page");
-        assert_eq!(free_page, 3);
+        if self.physical_memory[frame].kind != FrameKind::Page {
+            self.move_children(frame, |offset| EntryLocation::Disk { block, offset });
+        }
+
+        // Inverted table pages have no owning entry, only a place in a chain
+        if let Some(inverted) = &mut self.inverted {
+            let key = inverted.remove(&mut self.physical_memory, frame);
+            inverted.swap_out(key, block);
+        }
+
+        if let Some(owner) = self.physical_memory[frame].owner.take() {
+            self.set_entry(owner, -i32::try_from(block)?);
+        }
+
+        // Pages are only ever loaded into free or evicted frames, so dropping
+        // the evicted frame keeps the TLB coherent across page in and out.
+        if let Some(tlb) = &mut self.tlb {
+            tlb.invalidate_frame(frame);
+        }
+
+        let evicted = &mut self.physical_memory[frame];
+        evicted.refs = 0;
+        evicted.referenced = false;
+        evicted.block = None;
+
+        Ok(())
     }
 
-    #[test]
-    fn find_free_page_full() {
-        let mut vm = VirtualMemory::new(vec![], vec![]).expect("Failed to init");
+    /// Points every entry mapping shared `frame` at a block of its own, the
+    /// first at `block` and the others at copies of it, since a write through
+    /// one of them once the page is back must not show through the others.
+    ///
+    /// # Errors
+    /// - `VMError::DiskFull` if there are not enough blocks for the copies,
+    ///   in which case no entry is changed.
+    fn evict_shared(&mut self, frame: usize, block: usize) -> VMResult<()> {
+        let entries = self.sharer_entries(frame)?;
+
+        let mut blocks = vec![block];
+        for _ in 1..entries.len() {
+            match self.copy_block(block) {
+                Ok(copy) => blocks.push(copy),
+                Err(error) => {
+                    for &copy in &blocks[1..] {
+                        self.disk.free(copy);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        for ((_, entry), block) in entries.into_iter().zip(blocks) {
+            self.set_entry(entry, -i32::try_from(block)?);
+        }
+        self.sharers.remove(&frame);
+        self.physical_memory[frame].owner = None;
 
-        for i in 0..PAGE_COUNT {
-            vm.physical_memory[i].free = false;
+        Ok(())
+    }
+
+    /// Writes every modified resident frame back to disk.
+    pub fn flush(&mut self) -> VMResult<()> {
+        for frame in 0..self.physical_memory.len() {
+            let current = &self.physical_memory[frame];
+            if !current.is_free() && current.dirty && !self.is_pinned(current.kind) {
+                self.write_back(frame)?;
+            }
         }
 
-        let free_page = vm.allocate_page();
-        assert_eq!(free_page.unwrap_err(), VMError::MemoryFull);
+        Ok(())
+    }
+
+    fn get_frame(&mut self, address: usize, kind: FrameKind, pinned: &[usize]) -> VMResult<usize> {
+        match self.physical_memory.get_word_by_address(address) {
+            // Not Resident in Memory
+            frame if frame < 0 => {
+                let disk_frame = usize::try_from(frame.abs())?;
+                let free_frame = self.allocate_page(pinned)?;
+
+                self.set_word(address, i32::try_from(free_frame)?);
+                self.stats.disk_reads += 1;
+                match kind {
+                    FrameKind::Page => self.stats.page_faults += 1,
+                    _ => self.stats.page_table_faults += 1,
+                }
+                if let Some(page_ins) = &mut self.page_ins {
+                    page_ins.push(PageIn {
+                        kind,
+                        block: Some(disk_frame),
+                        frame: free_frame,
+                    });
+                }
+
+                // Copy Frame From Disk to Memory
+                for (i, &word) in self.disk[disk_frame].iter().enumerate() {
+                    self.physical_memory.set_word_by_offset(free_frame, i, word);
+                }
+
+                let loaded = &mut self.physical_memory[free_frame];
+                loaded.kind = kind;
+                loaded.owner = Some(EntryLocation::Memory(address));
+                loaded.referenced = true;
+                loaded.dirty = false;
+                loaded.block = Some(disk_frame);
+
+                if kind != FrameKind::Page {
+                    let page_size = self.geometry.page_size();
+                    self.move_children(free_frame, |offset| {
+                        EntryLocation::Memory(free_frame * page_size + offset)
+                    });
+                }
+
+                self.policy.loaded(free_frame);
+
+                Ok(free_frame)
+            }
+            // Not Initialized
+            0 if self.demand_zero => {
+                let frame = self.allocate_zeroed_page(EntryLocation::Memory(address), kind)?;
+                self.physical_memory[frame].referenced = true;
+                self.stats.zero_fills += 1;
+                if let Some(page_ins) = &mut self.page_ins {
+                    page_ins.push(PageIn {
+                        kind,
+                        block: None,
+                        frame,
+                    });
+                }
+                Ok(frame)
+            }
+            0 => Err(VMError::MemoryNotInitialized),
+
+            // Resident in Memory
+            offset => {
+                let frame = usize::try_from(offset)?;
+                self.physical_memory[frame].referenced = true;
+                self.policy.accessed(frame);
+                Ok(frame)
+            }
+        }
+    }
+
+    pub fn translate(&mut self, virtual_address: VirtualAddress) -> VMResult<Address> {
+        let result = self.walk(virtual_address);
+
+        self.stats.translations += 1;
+        match result {
+            Err(VMError::VirtualAddressOutOfBounds) => self.stats.out_of_bounds += 1,
+            Err(VMError::MemoryNotInitialized) => self.stats.not_initialized += 1,
+            _ => {}
+        }
+
+        result
+    }
+
+    /// Translates `virtual_address`, recording the segment table entry it
+    /// started from and every page table or page loaded on the way.
+    pub fn translate_traced(&mut self, virtual_address: VirtualAddress) -> Trace {
+        let (segment_size, page_table) = self.segment(virtual_address.s).unwrap_or_default();
+
+        self.page_ins = Some(Vec::new());
+        let result = self.translate(virtual_address);
+
+        Trace {
+            virtual_address,
+            segment_size,
+            page_table,
+            page_ins: self.page_ins.take().unwrap_or_default(),
+            result,
+        }
+    }
+
+    /// Looks up `virtual_address` in the TLB, then the segment and page tables.
+    fn walk(&mut self, virtual_address: VirtualAddress) -> VMResult<Address> {
+        match self.scheme {
+            Scheme::Segmented => {}
+            Scheme::Radix { levels } => return self.walk_radix(virtual_address, levels),
+            Scheme::Inverted => return self.walk_inverted(virtual_address),
+            Scheme::PureSegmentation => return self.walk_pure_segmentation(virtual_address),
+            Scheme::PurePaging => return self.walk_pure_paging(virtual_address),
+        }
+
+        // A paged segment table without a page for the segment means size 0
+        let Some(size_address) =
+            self.resident_segment_entry(virtual_address.s, SEGMENT_SIZE_OFFSET)?
+        else {
+            return Err(VMError::VirtualAddressOutOfBounds);
+        };
+        let segment_size = self.physical_memory.get_word_by_address(size_address);
+        // Both words of an entry share a page
+        let page_table_address = size_address - SEGMENT_SIZE_OFFSET + SEGMENT_PAGE_TABLE_OFFSET;
+        let segment_table_frame = page_table_address / self.geometry.page_size();
+
+        if virtual_address.pw >= u32::try_from(segment_size)? {
+            return Err(VMError::VirtualAddressOutOfBounds);
+        }
+
+        let page_frame = if let Some(frame) = self.cached_frame(virtual_address) {
+            frame
+        } else {
+            let page_table_frame = self.get_frame(
+                page_table_address,
+                FrameKind::PageTable,
+                &[segment_table_frame],
+            )?;
+            let frame = self.get_frame(
+                page_table_frame * self.geometry.page_size() + usize::from(virtual_address.p),
+                FrameKind::Page,
+                &[segment_table_frame, page_table_frame],
+            )?;
+
+            if let Some(tlb) = &mut self.tlb {
+                tlb.insert(virtual_address.s, virtual_address.p, frame);
+            }
+            frame
+        };
+
+        Ok(u32::try_from(
+            page_frame * self.geometry.page_size() + usize::from(virtual_address.w),
+        )?)
+    }
+
+    /// Page frame the TLB holds for `virtual_address`, if any, marked as used.
+    fn cached_frame(&mut self, virtual_address: VirtualAddress) -> Option<usize> {
+        let frame = self
+            .tlb
+            .as_mut()?
+            .lookup(virtual_address.s, virtual_address.p)?;
+        self.physical_memory[frame].referenced = true;
+        self.policy.accessed(frame);
+
+        Some(frame)
+    }
+
+    /// Switches to `process` and translates `virtual_address` in its address space.
+    pub fn translate_for(
+        &mut self,
+        process: ProcessId,
+        virtual_address: VirtualAddress,
+    ) -> VMResult<Address> {
+        self.switch_to(process)?;
+        self.translate(virtual_address)
+    }
+
+    /// Translates `virtual_address` after checking that `access` is allowed.
+    ///
+    /// # Errors
+    /// - `VMError::ProtectionFault` if the segment or page does not grant `access`.
+    pub fn translate_access(
+        &mut self,
+        virtual_address: VirtualAddress,
+        access: Access,
+    ) -> VMResult<Address> {
+        // Out of bounds addresses have no page to be protected
+        let in_bounds = match self.scheme {
+            Scheme::Segmented | Scheme::PureSegmentation => {
+                let segment_size =
+                    self.segment_word(self.active, virtual_address.s, SEGMENT_SIZE_OFFSET)?;
+                virtual_address.pw < u32::try_from(segment_size)?
+            }
+            Scheme::PurePaging => self
+                .paging_entry(
+                    self.active,
+                    page_number(&self.geometry, virtual_address.s, virtual_address.p),
+                )
+                .is_some(),
+            Scheme::Radix { .. } | Scheme::Inverted => true,
+        };
+        if in_bounds
+            && !self
+                .permissions(virtual_address.s, virtual_address.p)
+                .allows(access)
+        {
+            return Err(VMError::ProtectionFault);
+        }
+
+        self.translate(virtual_address)
+    }
+
+    /// Reads the word mapped by `virtual_address`, paging in as needed.
+    pub fn read_word(&mut self, virtual_address: VirtualAddress) -> VMResult<i32> {
+        let address = usize::try_from(self.translate_access(virtual_address, Access::Read)?)?;
+
+        Ok(self.physical_memory.get_word_by_address(address))
+    }
+
+    /// Writes `value` to the word mapped by `virtual_address`, paging in as needed.
+    pub fn write_word(&mut self, virtual_address: VirtualAddress, value: i32) -> VMResult<()> {
+        let address = usize::try_from(self.translate_access(virtual_address, Access::Write)?)?;
+        let address = self.copy_on_write(virtual_address, address)?;
+        self.set_word(address, value);
+
+        Ok(())
+    }
+
+    /// Gives the active process its own copy of the page holding `address`
+    /// if the page is shared, returning where `address` is now.
+    fn copy_on_write(
+        &mut self,
+        virtual_address: VirtualAddress,
+        address: usize,
+    ) -> VMResult<usize> {
+        let page_size = self.geometry.page_size();
+        let shared = address / page_size;
+        if !self.physical_memory[shared].is_shared() {
+            return Ok(address);
+        }
+
+        // The translation left the page table resident, and the shared page
+        // must stay in place until it is copied
+        let entry = self.page_table_entry(virtual_address.s, usize::from(virtual_address.p))?;
+        let pinned = match entry {
+            EntryLocation::Memory(entry_address) => vec![shared, entry_address / page_size],
+            EntryLocation::Disk { .. } => vec![shared],
+        };
+
+        let frame = self.allocate_page(&pinned)?;
+        self.physical_memory[frame] = Frame {
+            refs: 1,
+            kind: FrameKind::Page,
+            owner: Some(entry),
+            referenced: true,
+            dirty: true,
+            block: None,
+            data: self.physical_memory[shared].data.clone(),
+        };
+        self.set_entry(entry, i32::try_from(frame)?);
+        self.policy.loaded(frame);
+        self.release_frame(shared)?;
+
+        Ok(frame * page_size + address % page_size)
+    }
+
+    /// Size and page table words of segment `s`'s entry in the active
+    /// process, or its limit and base with pure segmentation.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidSegment` if `s` is past the last segment.
+    /// - `VMError::UnsupportedScheme` without segment tables.
+    pub fn segment(&self, s: u16) -> VMResult<(i32, i32)> {
+        self.check_segment_table()?;
+        if usize::from(s) > self.geometry.max_segment() {
+            return Err(VMError::InvalidSegment);
+        }
+
+        Ok((
+            self.segment_word(self.active, s, SEGMENT_SIZE_OFFSET)?,
+            self.segment_word(self.active, s, SEGMENT_PAGE_TABLE_OFFSET)?,
+        ))
+    }
+
+    /// Entry for page `p` of segment `s` in the active process, read from
+    /// memory or disk without paging the page table in.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidSegment` if `s` is past the last segment.
+    /// - `VMError::InvalidPage` if `p` is past the last page.
+    /// - `VMError::SegmentNotFound` if `s` has no page table.
+    /// - `VMError::UnsupportedScheme` without segment and page tables.
+    pub fn page_entry(&self, s: u16, p: u16) -> VMResult<i32> {
+        self.check_segmented()?;
+        self.segment(s)?;
+        if usize::from(p) > self.geometry.max_page() {
+            return Err(VMError::InvalidPage);
+        }
+
+        Ok(self.get_entry(self.page_table_entry(s, usize::from(p))?))
+    }
+
+    #[must_use]
+    pub fn frame(&self, frame: usize) -> Option<&Frame> {
+        self.physical_memory.get(frame)
+    }
+
+    #[must_use]
+    pub const fn disk(&self) -> &Disk {
+        &self.disk
+    }
+
+    #[must_use]
+    pub fn free_frames(&self) -> usize {
+        self.physical_memory
+            .iter()
+            .filter(|frame| frame.is_free())
+            .count()
+    }
+
+    #[must_use]
+    pub fn used_disk_blocks(&self) -> usize {
+        self.disk.used_blocks().count()
+    }
+
+    #[must_use]
+    pub fn free_disk_blocks(&self) -> usize {
+        self.disk.free_count()
+    }
+
+    /// Current size of segment `s` in the active process.
+    pub fn segment_size(&self, s: u16) -> VMResult<u32> {
+        self.check_segment_table()?;
+        Ok(u32::try_from(self.segment_word(
+            self.active,
+            s,
+            SEGMENT_SIZE_OFFSET,
+        )?)?)
+    }
+
+    /// Creates segment `s` of `size` words in the active process, with a
+    /// fresh page table and zeroed pages.
+    ///
+    /// # Errors
+    /// - `VMError::SegmentExists` if `s` already has a page table.
+    /// - `VMError::InvalidSegmentSize` if `size` does not fit in a segment.
+    /// - `VMError::UnsupportedScheme` without segment tables.
+    pub fn create_segment(&mut self, s: u16, size: u32) -> VMResult<()> {
+        self.check_segmented()?;
+        self.check_segment_size(s, size)?;
+
+        let page_table_entry =
+            self.allocate_segment_entry(self.active, s, SEGMENT_PAGE_TABLE_OFFSET)?;
+        if self.get_entry(page_table_entry) != 0 {
+            return Err(VMError::SegmentExists);
+        }
+        self.allocate_zeroed_page(page_table_entry, FrameKind::PageTable)?;
+
+        self.resize_segment(s, size)
+    }
+
+    /// Grows or shrinks segment `s` of the active process to `size` words.
+    ///
+    /// Pages that come into range are allocated zeroed; pages that fall out
+    /// of range release their frame and disk block.
+    ///
+    /// # Errors
+    /// - `VMError::SegmentNotFound` if `s` has no page table.
+    /// - `VMError::InvalidSegmentSize` if `size` does not fit in a segment.
+    /// - `VMError::UnsupportedScheme` without segment tables.
+    pub fn resize_segment(&mut self, s: u16, size: u32) -> VMResult<()> {
+        self.check_segmented()?;
+        self.check_segment_size(s, size)?;
+        // Fails early for a missing segment even when no entry changes
+        self.page_table_entry(s, 0)?;
+
+        let page_count = usize::try_from(size)?.div_ceil(self.geometry.page_size());
+        let current_count =
+            usize::try_from(self.segment_size(s)?)?.div_ceil(self.geometry.page_size());
+
+        for page in current_count..page_count {
+            let entry = self.page_table_entry(s, page)?;
+            if self.get_entry(entry) == 0 {
+                self.allocate_zeroed_page(entry, FrameKind::Page)?;
+            }
+        }
+
+        // Clear every entry past the end, including any left by the init file
+        for page in page_count..self.geometry.pages_per_segment() {
+            let entry = self.page_table_entry(s, page)?;
+            let value = self.get_entry(entry);
+            self.set_entry(entry, 0);
+            self.release_entry(value)?;
+        }
+
+        let size_entry = self.allocate_segment_entry(self.active, s, SEGMENT_SIZE_OFFSET)?;
+        self.set_entry(size_entry, i32::try_from(size)?);
+
+        Ok(())
+    }
+
+    /// Destroys segment `s` of the active process, returning its frames to
+    /// the free pool and its disk blocks to the disk.
+    ///
+    /// # Errors
+    /// - `VMError::SegmentNotFound` if `s` has no page table.
+    /// - `VMError::UnsupportedScheme` without segment tables.
+    pub fn destroy_segment(&mut self, s: u16) -> VMResult<()> {
+        self.resize_segment(s, 0)?;
+
+        let page_table_entry =
+            self.allocate_segment_entry(self.active, s, SEGMENT_PAGE_TABLE_OFFSET)?;
+        let page_table = self.get_entry(page_table_entry);
+        self.set_entry(page_table_entry, 0);
+        self.release_entry(page_table)?;
+        self.address_spaces[self.active].clear_protection(s);
+
+        Ok(())
+    }
+
+    fn check_segment_size(&self, s: u16, size: u32) -> VMResult<()> {
+        if usize::from(s) > self.geometry.max_segment() {
+            return Err(VMError::InvalidSegment);
+        }
+
+        // A segment can neither outgrow memory nor the pages its table maps
+        let max_size = self
+            .geometry
+            .max_segment_size()
+            .min(self.geometry.pages_per_segment() * self.geometry.page_size());
+        if usize::try_from(size)? > max_size {
+            return Err(VMError::InvalidSegmentSize);
+        }
+
+        Ok(())
+    }
+
+    /// Location of page `page`'s entry in segment `s`'s page table, which may
+    /// be resident or on disk.
+    fn page_table_entry(&self, s: u16, page: usize) -> VMResult<EntryLocation> {
+        let page_table = self.segment_word(self.active, s, SEGMENT_PAGE_TABLE_OFFSET)?;
+
+        if page_table == 0 {
+            return Err(VMError::SegmentNotFound);
+        }
+        self.entry_location(page_table, page)
+    }
+
+    /// Location of word `offset` of segment `s`'s entry in `process`, which
+    /// may be on disk if the segment table is paged. `None` if the page that
+    /// would hold it was never allocated.
+    fn segment_entry(
+        &self,
+        process: ProcessId,
+        s: u16,
+        offset: usize,
+    ) -> VMResult<Option<EntryLocation>> {
+        let address_space = &self.address_spaces[process];
+        if !self.paged_segment_table {
+            return Ok(Some(EntryLocation::Memory(
+                address_space.segment_entry(s, offset),
+            )));
+        }
+
+        let (directory_entry, word) = address_space.directory_entry(s, offset);
+        match self.physical_memory.get_word_by_address(directory_entry) {
+            0 => Ok(None),
+            page => self.entry_location(page, word).map(Some),
+        }
+    }
+
+    /// Word `offset` of segment `s`'s entry in `process`, read from memory or
+    /// disk without paging anything in.
+    fn segment_word(&self, process: ProcessId, s: u16, offset: usize) -> VMResult<i32> {
+        Ok(self
+            .segment_entry(process, s, offset)?
+            .map_or(0, |entry| self.get_entry(entry)))
+    }
+
+    /// Location of word `offset` of segment `s`'s entry in `process`, first
+    /// allocating a zeroed segment table page to hold it if there is none.
+    fn allocate_segment_entry(
+        &mut self,
+        process: ProcessId,
+        s: u16,
+        offset: usize,
+    ) -> VMResult<EntryLocation> {
+        if let Some(entry) = self.segment_entry(process, s, offset)? {
+            return Ok(entry);
+        }
+
+        let (directory_entry, word) = self.address_spaces[process].directory_entry(s, offset);
+        let page = self.allocate_zeroed_page(
+            EntryLocation::Memory(directory_entry),
+            FrameKind::SegmentTable,
+        )?;
+
+        Ok(EntryLocation::Memory(
+            page * self.geometry.page_size() + word,
+        ))
+    }
+
+    /// Physical address of word `offset` of segment `s`'s entry in the active
+    /// process, paging its segment table page in if needed. `None` if the
+    /// page that would hold it was never allocated.
+    fn resident_segment_entry(&mut self, s: u16, offset: usize) -> VMResult<Option<usize>> {
+        let address_space = &self.address_spaces[self.active];
+        if !self.paged_segment_table {
+            return Ok(Some(address_space.segment_entry(s, offset)));
+        }
+
+        let (directory_entry, word) = address_space.directory_entry(s, offset);
+        if self.physical_memory.get_word_by_address(directory_entry) == 0 {
+            return Ok(None);
+        }
+        let page = self.get_frame(directory_entry, FrameKind::SegmentTable, &[])?;
+
+        Ok(Some(page * self.geometry.page_size() + word))
+    }
+
+    /// Location of entry `page` of the page table at `page_table`, a frame or
+    /// a negated disk block as stored in a segment table entry.
+    fn entry_location(&self, page_table: i32, page: usize) -> VMResult<EntryLocation> {
+        Ok(if page_table < 0 {
+            EntryLocation::Disk {
+                block: usize::try_from(page_table.abs())?,
+                offset: page,
+            }
+        } else {
+            EntryLocation::Memory(usize::try_from(page_table)? * self.geometry.page_size() + page)
+        })
+    }
+
+    /// Finds the pages mapping each shared frame by walking every page table,
+    /// for a `VirtualMemory` whose frames were set up without `fork`.
+    fn rebuild_sharers(&mut self) -> VMResult<()> {
+        self.sharers.clear();
+        if self.check_segmented().is_err() {
+            return Ok(());
+        }
+
+        for process in 0..self.address_spaces.len() {
+            for s in 0..u16::try_from(self.geometry.segment_count())? {
+                let page_table = self.segment_word(process, s, SEGMENT_PAGE_TABLE_OFFSET)?;
+                if page_table == 0 {
+                    continue;
+                }
+
+                for page in 0..self.geometry.pages_per_segment() {
+                    let entry = self.get_entry(self.entry_location(page_table, page)?);
+                    let frame = usize::try_from(entry).unwrap_or(0);
+                    if frame > 0
+                        && self
+                            .physical_memory
+                            .get(frame)
+                            .is_some_and(Frame::is_shared)
+                    {
+                        let key = (process, s, u16::try_from(page)?);
+                        self.sharers.entry(frame).or_default().push(key);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pages recorded as sharing `frame` that still map it, with their
+    /// page table entries.
+    fn sharer_entries(&self, frame: usize) -> VMResult<Vec<(PageKey, EntryLocation)>> {
+        let value = i32::try_from(frame)?;
+        let mut entries = Vec::new();
+
+        for &(process, s, page) in self.sharers.get(&frame).into_iter().flatten() {
+            let page_table = self.segment_word(process, s, SEGMENT_PAGE_TABLE_OFFSET)?;
+            if page_table == 0 {
+                continue;
+            }
+
+            let entry = self.entry_location(page_table, usize::from(page))?;
+            if self.get_entry(entry) == value {
+                entries.push(((process, s, page), entry));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn get_entry(&self, entry: EntryLocation) -> i32 {
+        match entry {
+            EntryLocation::Memory(address) => self.physical_memory.get_word_by_address(address),
+            EntryLocation::Disk { block, offset } => self.disk[block][offset],
+        }
+    }
+
+    /// Maps a freshly zeroed frame holding a `kind` at `entry`.
+    fn allocate_zeroed_page(&mut self, entry: EntryLocation, kind: FrameKind) -> VMResult<usize> {
+        // Keep a resident page table in place while its page is allocated
+        let pinned = match entry {
+            EntryLocation::Memory(address) => vec![address / self.geometry.page_size()],
+            EntryLocation::Disk { .. } => vec![],
+        };
+
+        let frame = self.allocate_page(&pinned)?;
+        self.physical_memory[frame] = Frame {
+            refs: 1,
+            kind,
+            owner: Some(entry),
+            ..Frame::new(self.geometry.page_size())
+        };
+        self.set_entry(entry, i32::try_from(frame)?);
+        self.policy.loaded(frame);
+
+        Ok(frame)
+    }
+
+    /// Frees whatever a table entry points at: a resident frame or a disk block.
+    fn release_entry(&mut self, value: i32) -> VMResult<()> {
+        match value {
+            0 => {}
+            block if block < 0 => self.disk.free(usize::try_from(block.abs())?),
+            frame => self.release_frame(usize::try_from(frame)?)?,
+        }
+
+        Ok(())
+    }
+
+    /// Drops a reference to `frame` once the entry mapping it has been
+    /// changed, returning the frame and its disk block to the free pools when
+    /// nothing maps it anymore.
+    fn release_frame(&mut self, frame: usize) -> VMResult<()> {
+        if let Some(tlb) = &mut self.tlb {
+            tlb.invalidate_frame(frame);
+        }
+
+        if self.physical_memory[frame].is_shared() {
+            self.physical_memory[frame].refs -= 1;
+            let entries = self.sharer_entries(frame)?;
+            if self.physical_memory[frame].is_shared() {
+                self.sharers
+                    .insert(frame, entries.into_iter().map(|(key, _)| key).collect());
+            } else {
+                self.sharers.remove(&frame);
+                self.physical_memory[frame].owner = entries.first().map(|&(_, entry)| entry);
+            }
+            return Ok(());
+        }
+
+        self.policy.released(frame);
+        if let Some(block) = self.physical_memory[frame].block {
+            self.disk.free(block);
+        }
+        self.physical_memory[frame] = Frame::new(self.geometry.page_size());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vm::{
+        replacement::{Clock, Lru},
+        tlb::TlbReplacement,
+    };
+
+    use super::*;
+
+    fn before() -> VirtualMemory {
+        let st_inputs = vec![
+            STInput::new("8", "4000", "3", &Geometry::default()).expect("Failed to create PTInput"),
+            STInput::new("9", "5000", "-7", &Geometry::default())
+                .expect("Failed to create PTInput"),
+        ];
+        let pt_inputs = vec![
+            PTInput::new("8", "0", "10", &Geometry::default()).expect("Failed to create PTInput"),
+            PTInput::new("8", "1", "-20", &Geometry::default()).expect("Failed to create PTInput"),
+            PTInput::new("9", "0", "13", &Geometry::default()).expect("Failed to create PTInput"),
+            PTInput::new("9", "1", "-25", &Geometry::default()).expect("Failed to create PTInput"),
+        ];
+
+        VirtualMemory::new(st_inputs, pt_inputs).expect("Failed to init")
+    }
+
+    #[test]
+    fn init() {
+        let virtual_memory = before();
+
+        assert_eq!(virtual_memory.physical_memory[0].data[8 * 2], 4000);
+        assert_eq!(virtual_memory.physical_memory[0].data[8 * 2 + 1], 3);
+        assert_eq!(virtual_memory.physical_memory[0].data[9 * 2], 5000);
+        assert_eq!(virtual_memory.physical_memory[0].data[9 * 2 + 1], -7);
+
+        assert_eq!(virtual_memory.disk[7][0], 13);
+        assert_eq!(virtual_memory.disk[7][1], -25);
+
+        assert_eq!(virtual_memory.physical_memory[3].data[0], 10);
+        assert_eq!(virtual_memory.physical_memory[3].data[1], -20);
+    }
+
+    #[test]
+    fn init_undeclared_segment() {
+        let geometry = Geometry::default();
+        let st_inputs =
+            vec![STInput::new("8", "4000", "3", &geometry).expect("Failed to create STInput")];
+        let pt_inputs =
+            vec![PTInput::new("7", "0", "10", &geometry).expect("Failed to create PTInput")];
+
+        // The page would otherwise land in the segment table in frame 0
+        assert_eq!(
+            VirtualMemory::new(st_inputs, pt_inputs),
+            Err(VMError::SegmentNotFound)
+        );
+    }
+
+    #[test]
+    fn simple_translate() {
+        let mut vm = before();
+        let virtual_address = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        let expected_address = 5130;
+
+        let address = vm.translate(virtual_address).expect("Failed to translate");
+
+        assert_eq!(address, expected_address);
+    }
+
+    #[test]
+    fn pg_not_resident() {
+        let mut vm = before();
+        let virtual_address = VirtualAddress::new(2_097_674, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        let expected_address = 1034;
+
+        let address = vm.translate(virtual_address).expect("Failed to translate");
+
+        assert_eq!(address, expected_address);
+    }
+
+    #[test]
+    fn pt_not_resident() {
+        let mut vm = before();
+        let virtual_address = VirtualAddress::new(2_359_306, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        let expected_address = 6666;
+
+        let address = vm.translate(virtual_address).expect("Failed to translate");
+
+        assert_eq!(address, expected_address);
+    }
+
+    #[test]
+    fn pt_and_pg_not_resident() {
+        let mut vm = before();
+        let virtual_address = VirtualAddress::new(2_359_818, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        let expected_address = 2058;
+
+        let address = vm.translate(virtual_address).expect("Failed to translate");
+
+        assert_eq!(address, expected_address);
+    }
+
+    #[test]
+    fn find_free_page() {
+        let mut vm = VirtualMemory::new(vec![], vec![]).expect("Failed to init");
+
+        let free_page = vm.allocate_page(&[]).expect("Failed to find free page");
+        assert_eq!(free_page, 2);
+
+        let free_page = vm.allocate_page(&[]).expect("Failed to find free page");
+        assert_eq!(free_page, 3);
+    }
+
+    #[test]
+    fn find_free_page_full() {
+        let mut vm = VirtualMemory::new(vec![], vec![]).expect("Failed to init");
+
+        for i in 0..vm.geometry.frame_count() {
+            vm.physical_memory[i].refs = 1;
+        }
+
+        let free_page = vm.allocate_page(&[]);
+        assert_eq!(free_page, Err(VMError::MemoryFull));
+    }
+
+    #[test]
+    fn read_write() {
+        let mut vm = before();
+        let virtual_address = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        assert_eq!(vm.read_word(virtual_address), Ok(0));
+
+        vm.write_word(virtual_address, 42)
+            .expect("Failed to write word");
+
+        assert_eq!(vm.read_word(virtual_address), Ok(42));
+        assert_eq!(vm.physical_memory.get_word_by_address(5130), 42);
+    }
+
+    #[test]
+    fn read_write_not_resident() {
+        let mut vm = before();
+        vm.disk[25][10] = 7;
+        let virtual_address = VirtualAddress::new(2_359_818, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        assert_eq!(vm.read_word(virtual_address), Ok(7));
+        vm.write_word(virtual_address, -3)
+            .expect("Failed to write word");
+        assert_eq!(vm.read_word(virtual_address), Ok(-3));
+    }
+
+    #[test]
+    fn read_write_out_of_bounds() {
+        let mut vm = before();
+        let virtual_address = VirtualAddress::new(2_101_152, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        assert_eq!(
+            vm.read_word(virtual_address),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+        assert_eq!(
+            vm.write_word(virtual_address, 1),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn write_survives_eviction() {
+        let mut vm = before_full(Box::new(Fifo::new()));
+        let virtual_address = VirtualAddress::new(2_359_306, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        vm.write_word(virtual_address, 99)
+            .expect("Failed to write word");
+
+        // Segment 8 evicts segment 9's page, bringing segment 9 back restores it.
+        vm.translate(
+            VirtualAddress::new(2_097_162, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+        )
+        .expect("Failed to translate");
+        assert_eq!(vm.read_word(virtual_address), Ok(99));
+    }
+
+    #[test]
+    fn evict_disk_full() {
+        let mut vm = before_full(Box::new(Fifo::new()));
+        let virtual_address = VirtualAddress::new(2_359_306, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        vm.write_word(virtual_address, 99)
+            .expect("Failed to write word");
+        for block in 0..vm.geometry.frame_count() {
+            vm.disk.claim(block);
+        }
+
+        // The page from the init file has never been on disk, so it needs a block
+        assert_eq!(
+            vm.translate(
+                VirtualAddress::new(2_097_162, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            ),
+            Err(VMError::DiskFull)
+        );
+        assert_eq!(vm.free_disk_blocks(), 0);
+
+        // The page in frame 10 stays resident and a candidate
+        assert_eq!(vm.physical_memory[10].kind, FrameKind::Page);
+        assert!(vm.policy.state().contains(&10));
+
+        // Once blocks are free, frame 10 is still the first victim: segment
+        // 8's page table takes it, and its page evicts segment 9's page from
+        // frame 13
+        vm.disk.free(30);
+        vm.disk.free(31);
+        assert_eq!(
+            vm.translate(
+                VirtualAddress::new(2_097_162, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            ),
+            Ok(6666)
+        );
+        assert_eq!(vm.physical_memory.get_word_by_offset(10, 0), 13);
+        assert_eq!(vm.physical_memory.get_word_by_offset(3, 0), -31);
+        assert_eq!(vm.read_word(virtual_address), Ok(99));
+    }
+
+    #[test]
+    fn evict_disk_full_keeps_order() {
+        let policies: [Box<dyn ReplacementPolicy>; 3] = [
+            Box::new(Fifo::new()),
+            Box::new(Lru::new()),
+            Box::new(Clock::new()),
+        ];
+
+        for policy in policies {
+            let mut vm = before_full(policy);
+            let virtual_address = VirtualAddress::new(2_359_306, &Geometry::default())
+                .expect("Failed to create VirtualAddress");
+            vm.translate(virtual_address).expect("Failed to translate");
+            for block in 0..vm.geometry.frame_count() {
+                vm.disk.claim(block);
+            }
+
+            let state = vm.policy.state();
+            let referenced: Vec<bool> = vm
+                .physical_memory
+                .iter()
+                .map(|frame| frame.referenced)
+                .collect();
+            assert_eq!(
+                vm.translate(
+                    VirtualAddress::new(2_097_162, &Geometry::default())
+                        .expect("Failed to create VirtualAddress"),
+                ),
+                Err(VMError::DiskFull)
+            );
+
+            // The failed allocation leaves the next victim where it was
+            assert_eq!(vm.policy.state(), state, "{}", vm.policy.name());
+            assert!(vm
+                .physical_memory
+                .iter()
+                .map(|frame| frame.referenced)
+                .eq(referenced));
+        }
+    }
+
+    #[test]
+    fn demand_zero() {
+        let not_initialized = VirtualAddress::new(2_098_176, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        assert_eq!(
+            before().translate(not_initialized),
+            Err(VMError::MemoryNotInitialized)
+        );
+
+        let mut vm = before().with_demand_zero(true);
+        // Segment 10 has a size but no page table yet
+        let size_address = vm.address_spaces[0].segment_entry(10, SEGMENT_SIZE_OFFSET);
+        vm.set_word(size_address, 1000);
+
+        let virtual_address = VirtualAddress::new(2_621_445, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        assert_eq!(vm.translate(virtual_address), Ok(2053));
+        assert_eq!(vm.segment(10), Ok((1000, 2)));
+        assert_eq!(vm.physical_memory[2].kind, FrameKind::PageTable);
+        assert_eq!(vm.page_entry(10, 0), Ok(4));
+        assert_eq!(vm.read_word(virtual_address), Ok(0));
+
+        assert_eq!(vm.translate(not_initialized), Ok(2560));
+        assert_eq!(vm.page_entry(8, 2), Ok(5));
+        assert_eq!(vm.stats().not_initialized, 0);
+
+        let out_of_bounds = VirtualAddress::new(2_622_440, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        assert_eq!(
+            vm.translate(out_of_bounds),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn protection() {
+        let mut vm = before().with_protection(vec![
+            ProtectionInput::new("8", "*", "r-x", &Geometry::default())
+                .expect("Failed to create ProtectionInput"),
+            ProtectionInput::new("9", "1", "---", &Geometry::default())
+                .expect("Failed to create ProtectionInput"),
+        ]);
+        let code = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        let guard = VirtualAddress::new(2_359_818, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        let data = VirtualAddress::new(2_359_306, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        assert_eq!(vm.translate_access(code, Access::Execute), Ok(5130));
+        assert_eq!(vm.read_word(code), Ok(0));
+        assert_eq!(vm.write_word(code, 1), Err(VMError::ProtectionFault));
+
+        assert_eq!(vm.read_word(guard), Err(VMError::ProtectionFault));
+        assert_eq!(vm.write_word(data, 1), Ok(()));
+
+        // Unchecked translation ignores protection
+        assert_eq!(vm.translate(guard), Ok(2058));
+    }
+
+    #[test]
+    fn protection_out_of_bounds() {
+        let mut vm = before().with_protection(vec![ProtectionInput::new(
+            "8",
+            "*",
+            "---",
+            &Geometry::default(),
+        )
+        .expect("Failed to create ProtectionInput")]);
+        let virtual_address = VirtualAddress::new(2_101_152, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        assert_eq!(
+            vm.translate_access(virtual_address, Access::Read),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn permissions() {
+        let vm = before().with_protection(vec![
+            ProtectionInput::new("8", "*", "rw-", &Geometry::default())
+                .expect("Failed to create ProtectionInput"),
+            ProtectionInput::new("8", "1", "r-x", &Geometry::default())
+                .expect("Failed to create ProtectionInput"),
+        ]);
+
+        assert_eq!(vm.permissions(8, 0).to_string(), "rw-");
+        assert_eq!(vm.permissions(8, 1).to_string(), "r--");
+        assert_eq!(vm.permissions(9, 0), Permissions::ALL);
+    }
+
+    #[test]
+    fn tlb() {
+        let mut vm = before()
+            .with_tlb(TlbConfig {
+                entries: 4,
+                associativity: 4,
+                replacement: TlbReplacement::Lru,
+            })
+            .expect("Failed to add TLB");
+        let virtual_address = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        assert_eq!(vm.translate(virtual_address), Ok(5130));
+        assert_eq!(vm.translate(virtual_address), Ok(5130));
+        // Same page, different word
+        assert_eq!(
+            vm.translate(
+                VirtualAddress::new(2_097_152, &Geometry::default())
+                    .expect("Failed to create VirtualAddress")
+            ),
+            Ok(5120)
+        );
+
+        assert_eq!(vm.tlb_stats(), Some(TlbStats { hits: 2, misses: 1 }));
+        assert_eq!(before().tlb_stats(), None);
+    }
+
+    #[test]
+    fn tlb_invalidated_on_eviction() {
+        let mut vm = before_full(Box::new(Fifo::new()))
+            .with_tlb(TlbConfig {
+                entries: 4,
+                associativity: 2,
+                replacement: TlbReplacement::Fifo,
+            })
+            .expect("Failed to add TLB");
+        let virtual_address = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        assert_eq!(vm.translate(virtual_address), Ok(5130));
+
+        // Paging in segment 9 evicts frames 3 and 10, so segment 8 must miss again.
+        vm.translate(
+            VirtualAddress::new(2_359_818, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+        )
+        .expect("Failed to translate");
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8 * 2 + 1), -1);
+
+        vm.translate(virtual_address).expect("Failed to translate");
+        assert_eq!(vm.tlb_stats(), Some(TlbStats { hits: 0, misses: 3 }));
+    }
+
+    #[test]
+    fn create_process() {
+        let mut vm = before();
+        let process = vm
+            .create_process(
+                vec![STInput::new("8", "1000", "5", &Geometry::default())
+                    .expect("Failed to create STInput")],
+                vec![
+                    PTInput::new("8", "0", "2", &Geometry::default())
+                        .expect("Failed to create PTInput"),
+                    PTInput::new("8", "1", "-30", &Geometry::default())
+                        .expect("Failed to create PTInput"),
+                ],
+            )
+            .expect("Failed to create process");
+        assert_eq!(process, 1);
+        assert_eq!(vm.process_count(), 2);
+
+        // Frames 2 and 3 are claimed, so the segment table goes in 4 and 6.
+        assert_eq!(vm.address_spaces[1].segment_table, vec![4, 6]);
+        assert_eq!(vm.physical_memory[4].kind, FrameKind::SegmentTable);
+        assert_eq!(vm.physical_memory.get_word_by_offset(4, 8 * 2), 1000);
+        assert_eq!(vm.physical_memory.get_word_by_offset(4, 8 * 2 + 1), 5);
+        assert_eq!(vm.physical_memory.get_word_by_offset(5, 1), -30);
+        assert!(vm.disk.is_used(30));
+
+        // Creating a process does not switch to it
+        let virtual_address = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        assert_eq!(vm.active_process(), 0);
+        assert_eq!(vm.translate(virtual_address), Ok(5130));
+
+        assert_eq!(vm.translate_for(1, virtual_address), Ok(1034));
+        assert_eq!(vm.active_process(), 1);
+        assert_eq!(
+            vm.translate(
+                VirtualAddress::new(2_359_306, &Geometry::default())
+                    .expect("Failed to create VirtualAddress")
+            ),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+
+        assert_eq!(vm.translate_for(0, virtual_address), Ok(5130));
+    }
+
+    #[test]
+    fn fork() {
+        let mut vm = before();
+        let address = |address| {
+            VirtualAddress::new(address, &Geometry::default())
+                .expect("Failed to create VirtualAddress")
+        };
+
+        let child = vm.fork().expect("Failed to fork");
+        assert_eq!(child, 1);
+        assert_eq!(vm.active_process(), 0);
+
+        // Resident pages are shared, the page table and pages on disk copied
+        vm.switch_to(child).expect("Failed to switch");
+        assert_eq!(vm.segment(8), Ok((4000, 5)));
+        assert_eq!(vm.segment(9), Ok((5000, -2)));
+        assert_eq!(vm.page_entry(8, 0), Ok(10));
+        assert_eq!(vm.page_entry(8, 1), Ok(-1));
+        assert_eq!(vm.page_entry(9, 1), Ok(-3));
+        assert_eq!(vm.physical_memory[10].refs, 2);
+        assert_eq!(vm.physical_memory[13].refs, 2);
+
+        // The first write copies the page for the writer only
+        vm.write_word(address(2_097_162), 99)
+            .expect("Failed to write word");
+        assert_eq!(vm.page_entry(8, 0), Ok(6));
+        assert_eq!(vm.read_word(address(2_097_162)), Ok(99));
+        assert_eq!(vm.physical_memory[10].refs, 1);
+        assert_eq!(
+            vm.physical_memory[10].owner,
+            Some(EntryLocation::Memory(3 * 512))
+        );
+
+        vm.switch_to(0).expect("Failed to switch");
+        assert_eq!(vm.read_word(address(2_097_162)), Ok(0));
+        vm.write_word(address(2_359_306), 7)
+            .expect("Failed to write word");
+        assert_eq!(vm.page_entry(9, 0), Ok(8));
+        assert_eq!(
+            vm.physical_memory[13].owner,
+            Some(EntryLocation::Disk {
+                block: 2,
+                offset: 0
+            })
+        );
+
+        vm.switch_to(child).expect("Failed to switch");
+        assert_eq!(vm.read_word(address(2_359_306)), Ok(0));
+    }
+
+    #[test]
+    fn fork_release_shared() {
+        let mut vm = before();
+        let child = vm.fork().expect("Failed to fork");
+
+        // Destroying the child's segment leaves the parent's pages in place
+        vm.switch_to(child).expect("Failed to switch");
+        vm.destroy_segment(8).expect("Failed to destroy segment");
+        assert_eq!(vm.physical_memory[10].refs, 1);
+        assert!(vm.physical_memory[5].is_free());
+        assert!(!vm.disk.is_used(1));
+        assert!(vm.disk.is_used(20));
+
+        vm.switch_to(0).expect("Failed to switch");
+        vm.destroy_segment(8).expect("Failed to destroy segment");
+        assert!(vm.physical_memory[10].is_free());
+    }
+
+    #[test]
+    fn fork_evict_shared() {
+        let mut vm = before();
+        let address = |address| {
+            VirtualAddress::new(address, &Geometry::default())
+                .expect("Failed to create VirtualAddress")
+        };
+        vm.write_word(address(2_097_162), 42)
+            .expect("Failed to write word");
+        let child = vm.fork().expect("Failed to fork");
+
+        // Fill memory and leave the shared page as the only candidate
+        vm.physical_memory
+            .iter_mut()
+            .filter(|frame| frame.is_free())
+            .for_each(|frame| frame.refs = 1);
+        vm.policy.restore(&[10]);
+
+        // Loading the child's page table for segment 9 evicts the shared page,
+        // giving each process its own block
+        vm.switch_to(child).expect("Failed to switch");
+        assert_eq!(vm.translate(address(2_359_306)), Ok(6666));
+        assert_eq!(vm.physical_memory[10].kind, FrameKind::PageTable);
+        let child_block = vm.page_entry(8, 0).expect("Failed to get entry");
+        vm.switch_to(0).expect("Failed to switch");
+        let parent_block = vm.page_entry(8, 0).expect("Failed to get entry");
+        assert!(child_block < 0 && parent_block < 0);
+        assert_ne!(child_block, parent_block);
+        assert!(!vm.sharers.contains_key(&10));
+
+        // A write through one copy does not show through the other
+        vm.switch_to(child).expect("Failed to switch");
+        assert_eq!(vm.read_word(address(2_097_162)), Ok(42));
+        vm.write_word(address(2_097_162), 7)
+            .expect("Failed to write word");
+        vm.switch_to(0).expect("Failed to switch");
+        assert_eq!(vm.read_word(address(2_097_162)), Ok(42));
+        vm.switch_to(child).expect("Failed to switch");
+        assert_eq!(vm.read_word(address(2_097_162)), Ok(7));
+    }
+
+    #[test]
+    fn fork_evict_shared_disk_full() {
+        let mut vm = before();
+        let child = vm.fork().expect("Failed to fork");
+        vm.physical_memory
+            .iter_mut()
+            .filter(|frame| frame.is_free())
+            .for_each(|frame| frame.refs = 1);
+        vm.policy.restore(&[10]);
+
+        // One block is enough for the page but not for the child's copy
+        let free: Vec<usize> = (0..vm.geometry.frame_count())
+            .filter(|&block| !vm.disk.is_used(block))
+            .collect();
+        for &block in &free[1..] {
+            vm.disk.claim(block);
+        }
+
+        vm.switch_to(child).expect("Failed to switch");
+        assert_eq!(
+            vm.translate(
+                VirtualAddress::new(2_359_306, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            ),
+            Err(VMError::DiskFull)
+        );
+        assert_eq!(vm.page_entry(8, 0), Ok(10));
+        assert_eq!(vm.physical_memory[10].refs, 2);
+        assert!(vm.policy.state().contains(&10));
+        assert_eq!(vm.free_disk_blocks(), 0);
+    }
+
+    #[test]
+    fn fork_of_fork() {
+        let mut vm = before();
+        let address = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        let child = vm.fork().expect("Failed to fork");
+        vm.switch_to(child).expect("Failed to switch");
+        let grandchild = vm.fork().expect("Failed to fork");
+        assert_eq!(vm.physical_memory[10].refs, 3);
+        assert_eq!(
+            vm.sharers[&10],
+            vec![(0, 8, 0), (child, 8, 0), (grandchild, 8, 0)]
+        );
+
+        vm.write_word(address, 1).expect("Failed to write word");
+        assert_eq!(vm.physical_memory[10].refs, 2);
+        assert_eq!(vm.sharers[&10], vec![(0, 8, 0), (grandchild, 8, 0)]);
+
+        // Once only the grandchild maps the page, it owns it and writes in place
+        vm.switch_to(0).expect("Failed to switch");
+        vm.write_word(address, 2).expect("Failed to write word");
+        assert_eq!(vm.physical_memory[10].refs, 1);
+        assert!(!vm.sharers.contains_key(&10));
+        vm.switch_to(grandchild).expect("Failed to switch");
+        let (_, page_table) = vm.segment(8).expect("Failed to get segment");
+        assert_eq!(
+            vm.physical_memory[10].owner,
+            Some(EntryLocation::Memory(
+                usize::try_from(page_table).expect("Page table is resident") * 512
+            ))
+        );
+        vm.write_word(address, 3).expect("Failed to write word");
+        assert_eq!(vm.page_entry(8, 0), Ok(10));
+
+        for (process, value) in [(0, 2), (child, 1), (grandchild, 3)] {
+            vm.switch_to(process).expect("Failed to switch");
+            assert_eq!(vm.read_word(address), Ok(value));
+        }
+    }
+
+    #[test]
+    fn switch_to_invalid_process() {
+        let mut vm = before();
+        assert_eq!(vm.switch_to(1), Err(VMError::InvalidProcess));
+        assert_eq!(vm.active_process(), 0);
+    }
+
+    #[test]
+    fn switch_flushes_tlb() {
+        let mut vm = before()
+            .with_tlb(TlbConfig {
+                entries: 4,
+                associativity: 4,
+                replacement: TlbReplacement::Lru,
+            })
+            .expect("Failed to add TLB");
+        let process = vm
+            .create_process(
+                vec![STInput::new("8", "1000", "5", &Geometry::default())
+                    .expect("Failed to create STInput")],
+                vec![PTInput::new("8", "0", "2", &Geometry::default())
+                    .expect("Failed to create PTInput")],
+            )
+            .expect("Failed to create process");
+        let virtual_address = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        assert_eq!(vm.translate(virtual_address), Ok(5130));
+        assert_eq!(vm.translate_for(process, virtual_address), Ok(1034));
+        assert_eq!(vm.translate_for(0, virtual_address), Ok(5130));
+        assert_eq!(vm.tlb_stats(), Some(TlbStats { hits: 0, misses: 3 }));
+    }
+
+    #[test]
+    fn protection_per_process() {
+        let mut vm = before().with_protection(vec![ProtectionInput::new(
+            "8",
+            "*",
+            "r--",
+            &Geometry::default(),
+        )
+        .expect("Failed to create ProtectionInput")]);
+        vm.create_process(
+            vec![STInput::new("8", "1000", "5", &Geometry::default())
+                .expect("Failed to create STInput")],
+            vec![PTInput::new("8", "0", "2", &Geometry::default())
+                .expect("Failed to create PTInput")],
+        )
+        .expect("Failed to create process");
+
+        assert_eq!(vm.permissions(8, 0).to_string(), "r--");
+        vm.switch_to(1).expect("Failed to switch process");
+        assert_eq!(vm.permissions(8, 0), Permissions::ALL);
+    }
+
+    #[test]
+    fn geometry() {
+        // 64 word pages, 16 pages per segment, 16 segments in a single frame
+        let geometry = Geometry::new(4, 4, 6, 16).expect("Failed to create Geometry");
+        let mut vm = VirtualMemory::with_geometry(
+            geometry,
+            vec![STInput::new("1", "200", "1", &geometry).expect("Failed to create STInput")],
+            vec![
+                PTInput::new("1", "0", "2", &geometry).expect("Failed to create PTInput"),
+                PTInput::new("1", "3", "-4", &geometry).expect("Failed to create PTInput"),
+            ],
+        )
+        .expect("Failed to init");
+
+        assert_eq!(vm.physical_memory.len(), 16);
+        assert_eq!(vm.physical_memory[0].data.len(), 64);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 3), 1);
+
+        let address =
+            |value| VirtualAddress::new(value, &geometry).expect("Failed to create VirtualAddress");
+        assert_eq!(vm.translate(address(1 << 10 | 1)), Ok(2 * 64 + 1));
+        assert_eq!(vm.translate(address(1 << 10 | 3 << 6 | 5)), Ok(3 * 64 + 5));
+        assert_eq!(
+            vm.translate(address(1 << 10 | 3 << 6 | 8)),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+        assert_eq!(
+            VirtualAddress::new(1 << 14, &geometry),
+            Err(VMError::VirtualAddressLeadingBits)
+        );
+    }
+
+    /// `before` with every other frame taken, so only the frames mapped by
+    /// the init (3, 10 and 13) can be evicted.
+    fn before_full(policy: Box<dyn ReplacementPolicy>) -> VirtualMemory {
+        let mut vm = before().with_policy(policy);
+        vm.physical_memory
+            .iter_mut()
+            .for_each(|frame| frame.refs = 1);
+        vm
+    }
+
+    #[test]
+    fn evict_fifo() {
+        let mut vm = before_full(Box::new(Fifo::new()));
+
+        // Segment 8's page table in frame 3 was loaded first.
+        let address = vm
+            .translate(
+                VirtualAddress::new(2_359_306, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            )
+            .expect("Failed to translate");
+        assert_eq!(address, 6666);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8 * 2 + 1), -1);
+        assert_eq!(vm.disk[1][0], 10);
+        assert_eq!(vm.disk[1][1], -20);
+        assert_eq!(vm.physical_memory[3].block, Some(7));
+
+        // Reloading segment 8's page table evicts its own page from frame 10,
+        // then the page evicts segment 9's page from frame 13. Neither page
+        // had a disk block, so they get blocks 2 and 3.
+        let address = vm
+            .translate(
+                VirtualAddress::new(2_097_162, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            )
+            .expect("Failed to translate");
+        assert_eq!(address, 6666);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8 * 2 + 1), 10);
+        assert_eq!(vm.physical_memory.get_word_by_offset(10, 0), 13);
+        assert_eq!(vm.physical_memory.get_word_by_offset(3, 0), -3);
+    }
+
+    #[test]
+    fn evict_lru() {
+        let mut vm = before_full(Box::new(Lru::new()));
+
+        // Use segment 8 so segment 9's page in frame 13 becomes least recently used.
+        vm.translate(
+            VirtualAddress::new(2_097_162, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+        )
+        .expect("Failed to translate");
+
+        let address = vm
+            .translate(
+                VirtualAddress::new(2_359_306, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            )
+            .expect("Failed to translate");
+        assert_eq!(address, 1546);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 9 * 2 + 1), 13);
+        assert_eq!(vm.physical_memory.get_word_by_offset(13, 0), 3);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8 * 2 + 1), -2);
+        assert_eq!(
+            vm.physical_memory[10].owner,
+            Some(EntryLocation::Disk {
+                block: 2,
+                offset: 0
+            })
+        );
+    }
+
+    #[test]
+    fn evict_clock() {
+        let mut vm = before_full(Box::new(Clock::new()));
+
+        // No frame has been referenced yet, so the hand stops at frame 3.
+        let address = vm
+            .translate(
+                VirtualAddress::new(2_359_306, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            )
+            .expect("Failed to translate");
+        assert_eq!(address, 6666);
+
+        // Frame 10 was never referenced, frame 13 was used by the first
+        // translation and gets a second chance before being taken.
+        let address = vm
+            .translate(
+                VirtualAddress::new(2_097_162, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            )
+            .expect("Failed to translate");
+        assert_eq!(address, 6666);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8 * 2 + 1), 10);
+        assert_eq!(vm.physical_memory.get_word_by_offset(3, 0), -3);
+        assert!(!vm.physical_memory[3].referenced);
+    }
+
+    #[test]
+    fn referenced_and_dirty() {
+        let mut vm = before();
+        let virtual_address = VirtualAddress::new(2_097_162, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        vm.read_word(virtual_address).expect("Failed to read word");
+        assert!(vm.physical_memory[10].referenced);
+        assert!(!vm.physical_memory[10].dirty);
+
+        vm.write_word(virtual_address, 1)
+            .expect("Failed to write word");
+        assert!(vm.physical_memory[10].dirty);
+
+        // Paging in segment 9's page table rewrites its segment table entry only.
+        vm.translate(
+            VirtualAddress::new(2_359_306, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+        )
+        .expect("Failed to translate");
+        assert_eq!(vm.physical_memory[2].block, Some(7));
+        assert!(!vm.physical_memory[2].dirty);
+    }
+
+    #[test]
+    fn clean_eviction_skips_write() {
+        let mut vm = before_full(Box::new(Fifo::new()));
+
+        // Load segment 9's page table from block 7, then scribble over the
+        // disk copy to detect any write back.
+        vm.translate(
+            VirtualAddress::new(2_359_306, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+        )
+        .expect("Failed to translate");
+        vm.disk[7][100] = 5;
+
+        vm.evict(3).expect("Failed to evict");
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 9 * 2 + 1), -7);
+        assert_eq!(vm.disk[7][100], 5);
+    }
+
+    #[test]
+    fn dirty_eviction_writes_home_block() {
+        let mut vm = before_full(Box::new(Fifo::new()));
+        let virtual_address = VirtualAddress::new(2_359_818, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        // Page 1 of segment 9 comes from block 25 and is written to.
+        vm.write_word(virtual_address, 8)
+            .expect("Failed to write word");
+        let frame = vm.physical_memory.get_word_by_offset(3, 1);
+        assert_eq!(frame, 10);
+
+        vm.evict(10).expect("Failed to evict");
+        assert_eq!(vm.physical_memory.get_word_by_offset(3, 1), -25);
+        assert_eq!(vm.disk[25][10], 8);
+    }
+
+    #[test]
+    fn flush() {
+        let mut vm = before();
+        vm.write_word(
+            VirtualAddress::new(2_359_818, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+            8,
+        )
+        .expect("Failed to write word");
+        vm.write_word(
+            VirtualAddress::new(2_097_162, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+            9,
+        )
+        .expect("Failed to write word");
+
+        vm.flush().expect("Failed to flush");
+
+        // The page from block 25 goes home, page 0 of segment 8 gets a new block.
+        assert_eq!(vm.disk[25][10], 8);
+        assert_eq!(vm.physical_memory[10].block, Some(1));
+        assert_eq!(vm.disk[1][10], 9);
+        assert!(vm
+            .physical_memory
+            .iter()
+            .all(|frame| !frame.dirty || frame.kind == FrameKind::SegmentTable));
+    }
+
+    #[test]
+    fn evict_page_table() {
+        let mut vm = before();
+        vm.physical_memory
+            .iter_mut()
+            .for_each(|frame| frame.refs = 1);
+
+        // Segment 8's page table in frame 3 is the oldest frame; evicting it
+        // moves the owner of page frame 10 onto the disk.
+        vm.evict(3).expect("Failed to evict");
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8 * 2 + 1), -1);
+        assert_eq!(
+            vm.physical_memory[10].owner,
+            Some(EntryLocation::Disk {
+                block: 1,
+                offset: 0
+            })
+        );
+
+        vm.evict(10).expect("Failed to evict");
+        assert_eq!(vm.disk[1][0], -2);
+    }
+
+    #[test]
+    fn stats() {
+        let mut vm = before();
+
+        for address in [2_097_152, 2_097_664, 2_359_808, 2_101_152, 2_098_176] {
+            let _ = vm.translate(
+                VirtualAddress::new(address, &Geometry::default())
+                    .expect("Failed to create VirtualAddress"),
+            );
+        }
+
+        assert_eq!(
+            vm.stats(),
+            Stats {
+                translations: 5,
+                page_table_faults: 1,
+                page_faults: 2,
+                zero_fills: 0,
+                out_of_bounds: 1,
+                not_initialized: 1,
+                frames_allocated: 3,
+                disk_reads: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn translate_traced() {
+        let mut vm = before();
+
+        let trace = vm.translate_traced(
+            VirtualAddress::new(2_359_818, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+        );
+        assert_eq!(trace.segment_size, 5000);
+        assert_eq!(trace.page_table, -7);
+        assert_eq!(
+            trace.page_ins,
+            vec![
+                PageIn {
+                    kind: FrameKind::PageTable,
+                    block: Some(7),
+                    frame: 2
+                },
+                PageIn {
+                    kind: FrameKind::Page,
+                    block: Some(25),
+                    frame: 4
+                }
+            ]
+        );
+        assert_eq!(trace.result, Ok(2058));
+
+        // Nothing is recorded outside a traced translation
+        vm.translate(
+            VirtualAddress::new(2_097_664, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+        )
+        .expect("Failed to translate");
+        assert_eq!(vm.page_ins, None);
+    }
+
+    #[test]
+    fn inspect() {
+        let vm = before();
+
+        assert_eq!(vm.segment(8), Ok((4000, 3)));
+        assert_eq!(vm.segment(512), Err(VMError::InvalidSegment));
+        assert_eq!(vm.page_entry(8, 1), Ok(-20));
+        assert_eq!(vm.page_entry(9, 1), Ok(-25));
+        assert_eq!(vm.page_entry(8, 512), Err(VMError::InvalidPage));
+        assert_eq!(vm.page_entry(5, 0), Err(VMError::SegmentNotFound));
+        assert_eq!(
+            vm.frame(3).map(|frame| frame.kind),
+            Some(FrameKind::PageTable)
+        );
+        assert_eq!(vm.frame(1024), None);
+        assert_eq!(vm.free_frames(), 1024 - 5);
+        assert_eq!(vm.used_disk_blocks(), 4);
+    }
+
+    #[test]
+    fn create_segment() {
+        let mut vm = before();
+
+        vm.create_segment(5, 1000)
+            .expect("Failed to create segment");
+        assert_eq!(vm.segment_size(5), Ok(1000));
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 5 * 2 + 1), 2);
+        assert_eq!(vm.physical_memory[2].kind, FrameKind::PageTable);
+        assert_eq!(vm.physical_memory.get_word_by_offset(2, 0), 4);
+        assert_eq!(vm.physical_memory.get_word_by_offset(2, 1), 5);
+
+        let address = VirtualAddress::new(1_311_232, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        assert_eq!(vm.translate(address), Ok(5 * 512));
+        assert_eq!(vm.read_word(address), Ok(0));
+
+        assert_eq!(vm.create_segment(5, 10), Err(VMError::SegmentExists));
+        assert_eq!(
+            vm.create_segment(6, 262_145),
+            Err(VMError::InvalidSegmentSize)
+        );
+        assert_eq!(vm.create_segment(512, 10), Err(VMError::InvalidSegment));
+    }
+
+    #[test]
+    fn paged_segment_table() {
+        let mut vm = before()
+            .with_paged_segment_table()
+            .expect("Failed to page segment table");
+        let address = |value| {
+            VirtualAddress::new(value, &Geometry::default())
+                .expect("Failed to create VirtualAddress")
+        };
+
+        // The first segment table page moved out of frame 0 for the directory
+        assert_eq!(vm.physical_memory[0].kind, FrameKind::SegmentDirectory);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 0), 2);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 1), 1);
+        assert_eq!(vm.physical_memory.get_word_by_offset(2, 8 * 2 + 1), 3);
+        assert_eq!(
+            vm.physical_memory[3].owner,
+            Some(EntryLocation::Memory(2 * 512 + 8 * 2 + 1))
+        );
+
+        // An evicted segment table page comes back on the next translation
+        vm.evict(2).expect("Failed to evict");
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 0), -1);
+        assert_eq!(
+            vm.physical_memory[3].owner,
+            Some(EntryLocation::Disk {
+                block: 1,
+                offset: 8 * 2 + 1
+            })
+        );
+        assert_eq!(vm.segment(8), Ok((4000, 3)));
+        assert_eq!(vm.translate(address(2_097_162)), Ok(5130));
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 0), 2);
+        assert_eq!(vm.stats().page_table_faults, 1);
+
+        // A new process only gets a directory, pages are added per segment
+        let process = vm
+            .create_process(vec![], vec![])
+            .expect("Failed to create process");
+        vm.switch_to(process).expect("Failed to switch");
+        let directory = vm.address_spaces[process].segment_table[0];
+        assert_eq!(
+            vm.physical_memory[directory].kind,
+            FrameKind::SegmentDirectory
+        );
+        assert_eq!(
+            vm.translate(address(2_097_162)),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+
+        vm.create_segment(300, 1000)
+            .expect("Failed to create segment");
+        let page = usize::try_from(vm.physical_memory.get_word_by_offset(directory, 1))
+            .expect("Failed to convert page");
+        assert_eq!(vm.physical_memory[page].kind, FrameKind::SegmentTable);
+        assert_eq!(vm.physical_memory.get_word_by_offset(directory, 0), 0);
+        assert_eq!(vm.segment_size(300), Ok(1000));
+    }
+
+    /// The init of `before` loaded with `scheme`.
+    fn before_scheme(scheme: Scheme) -> VirtualMemory {
+        let geometry = Geometry::default();
+        let st_inputs = vec![
+            STInput::new("8", "4000", "3", &geometry).expect("Failed to create STInput"),
+            STInput::new("9", "5000", "-7", &geometry).expect("Failed to create STInput"),
+        ];
+        let pt_inputs = vec![
+            PTInput::new("8", "0", "10", &geometry).expect("Failed to create PTInput"),
+            PTInput::new("8", "1", "-20", &geometry).expect("Failed to create PTInput"),
+            PTInput::new("9", "0", "13", &geometry).expect("Failed to create PTInput"),
+            PTInput::new("9", "1", "-25", &geometry).expect("Failed to create PTInput"),
+        ];
+
+        VirtualMemory::with_scheme(geometry, scheme, st_inputs, pt_inputs).expect("Failed to init")
+    }
+
+    #[test]
+    fn demand_zero_counted() {
+        let address = |value| {
+            VirtualAddress::new(value, &Geometry::default())
+                .expect("Failed to create VirtualAddress")
+        };
+
+        let mut vm = before().with_demand_zero(true);
+        let size_address = vm.address_spaces[0].segment_entry(10, SEGMENT_SIZE_OFFSET);
+        vm.set_word(size_address, 1000);
+
+        // Segment 10's page table and page are both zeroed
+        let trace = vm.translate_traced(address(2_621_445));
+        assert_eq!(
+            trace.page_ins,
+            vec![
+                PageIn {
+                    kind: FrameKind::PageTable,
+                    block: None,
+                    frame: 2
+                },
+                PageIn {
+                    kind: FrameKind::Page,
+                    block: None,
+                    frame: 4
+                }
+            ]
+        );
+        assert_eq!(trace.result, Ok(2053));
+
+        // Neither touching them again nor loading from disk counts
+        vm.translate(address(2_621_445))
+            .expect("Failed to translate");
+        vm.translate(address(2_359_818))
+            .expect("Failed to translate");
+        let stats = vm.stats();
+        assert_eq!(
+            (
+                stats.zero_fills,
+                stats.page_faults,
+                stats.page_table_faults,
+                stats.not_initialized
+            ),
+            (2, 1, 1, 0)
+        );
+        assert_eq!(before().stats().zero_fills, 0);
+
+        // A whole segment is one zero fill
+        let mut vm = before_scheme(Scheme::PureSegmentation).with_demand_zero(true);
+        let size_address = vm.address_spaces[0].segment_entry(10, SEGMENT_SIZE_OFFSET);
+        vm.set_word(size_address, 1000);
+        let trace = vm.translate_traced(address(2_621_445));
+        assert_eq!(
+            trace.page_ins,
+            vec![PageIn {
+                kind: FrameKind::Segment,
+                block: None,
+                frame: 11
+            }]
+        );
+        let stats = vm.stats();
+        assert_eq!(
+            (stats.zero_fills, stats.page_faults, stats.frames_allocated),
+            (1, 0, 2)
+        );
+
+        let mut vm = before_scheme(Scheme::Inverted).with_demand_zero(true);
+        let trace = vm.translate_traced(address(2_098_176));
+        assert_eq!(trace.page_ins.len(), 1);
+        assert_eq!(trace.page_ins[0].block, None);
+        assert_eq!(vm.stats().zero_fills, 1);
+    }
+
+    #[test]
+    fn radix() {
+        let mut vm = before_scheme(Scheme::Radix { levels: 2 });
+        let address = |value| {
+            VirtualAddress::new(value, &Geometry::default())
+                .expect("Failed to create VirtualAddress")
+        };
+
+        // The root in frame 0 indexes by segment, the page tables by page
+        assert_eq!(vm.physical_memory[0].kind, FrameKind::PageDirectory);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8), 1);
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 9), 2);
+        assert_eq!(vm.physical_memory.get_word_by_offset(1, 0), 10);
+        assert_eq!(vm.physical_memory.get_word_by_offset(1, 1), -20);
+        assert!(vm.physical_memory[3].is_free());
+
+        assert_eq!(vm.translate(address(2_097_162)), Ok(5130));
+        assert_eq!(vm.translate(address(2_097_674)), Ok(3 * 512 + 10));
+        assert_eq!(
+            vm.translate(address(2_098_176)),
+            Err(VMError::MemoryNotInitialized)
+        );
+        // Segment sizes are gone with the segment table
+        assert_eq!(
+            vm.translate(address(2_359_818 + 8 * 512)),
+            Err(VMError::MemoryNotInitialized)
+        );
+        assert_eq!(vm.stats().page_faults, 1);
+
+        assert_eq!(vm.segment(8), Err(VMError::UnsupportedScheme));
+        assert_eq!(vm.fork(), Err(VMError::UnsupportedScheme));
+        assert_eq!(vm.create_segment(5, 10), Err(VMError::UnsupportedScheme));
+    }
+
+    #[test]
+    fn radix_page_table_on_disk() {
+        let mut vm = before_scheme(Scheme::Radix { levels: 3 });
+        let address = VirtualAddress::new(2_359_306, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+
+        // Segment 9 page 0 is 9 << 9, or 1, 8 and 0 with 6 bits per level
+        let middle = usize::try_from(vm.physical_memory.get_word_by_offset(0, 1))
+            .expect("Failed to convert frame");
+        let last = usize::try_from(vm.physical_memory.get_word_by_offset(middle, 8))
+            .expect("Failed to convert frame");
+        assert_eq!(vm.physical_memory.get_word_by_offset(last, 0), 13);
+
+        vm.evict(last).expect("Failed to evict");
+        vm.evict(middle).expect("Failed to evict");
+        assert!(vm.physical_memory.get_word_by_offset(0, 1) < 0);
+
+        assert_eq!(vm.translate(address), Ok(13 * 512 + 10));
+        assert_eq!(vm.stats().page_table_faults, 2);
+        assert_eq!(
+            VirtualMemory::with_scheme(
+                Geometry::default(),
+                Scheme::Radix { levels: 1 },
+                vec![],
+                vec![]
+            )
+            .map(|_| ()),
+            Err(VMError::InvalidGeometry)
+        );
+    }
+
+    #[test]
+    fn pure_segmentation() {
+        let mut vm = before_scheme(Scheme::PureSegmentation);
+        let address = |value| {
+            VirtualAddress::new(value, &Geometry::default())
+                .expect("Failed to create VirtualAddress")
+        };
+
+        // Segment 8 takes frames 3 to 10 and segment 9 waits in blocks 7 to 16
+        assert_eq!(vm.segment(8), Ok((4000, 3 * 512)));
+        assert_eq!(vm.segment(9), Ok((5000, -7)));
+        assert_eq!(vm.physical_memory[10].kind, FrameKind::Segment);
+        assert!(vm.physical_memory[11].is_free());
+        assert!(vm.disk.is_used(16));
+
+        assert_eq!(vm.translate(address(2_097_162)), Ok(3 * 512 + 10));
+        assert_eq!(vm.translate(address(2_097_674)), Ok(3 * 512 + 522));
+        assert_eq!(
+            vm.translate(address(2_101_152)),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+
+        // Segment 9 is loaded whole into the first run of 10 free frames
+        vm.disk[8][0] = 7;
+        assert_eq!(vm.read_word(address(2_359_808)), Ok(7));
+        assert_eq!(vm.segment(9), Ok((5000, 11 * 512)));
+        let stats = vm.stats();
+        assert_eq!(
+            (stats.page_faults, stats.disk_reads, stats.frames_allocated),
+            (1, 10, 10)
+        );
+        assert_eq!(vm.page_entry(8, 0), Err(VMError::UnsupportedScheme));
+        assert_eq!(vm.fork(), Err(VMError::UnsupportedScheme));
+    }
+
+    #[test]
+    fn pure_segmentation_fragmentation() {
+        // 16 frames of 4 words, the segment table takes frames 0 and 1
+        let geometry = Geometry::new(2, 2, 2, 16).expect("Failed to create Geometry");
+        let address =
+            |value| VirtualAddress::new(value, &geometry).expect("Failed to create VirtualAddress");
+        let st_inputs = [
+            ("0", "16", "2"),
+            ("1", "8", "8"),
+            ("2", "12", "-1"),
+            ("3", "16", "-5"),
+        ]
+        .iter()
+        .map(|&(s, size, f)| STInput::new(s, size, f, &geometry))
+        .collect::<VMResult<Vec<_>>>()
+        .expect("Failed to create STInput");
+        let mut vm =
+            VirtualMemory::with_scheme(geometry, Scheme::PureSegmentation, st_inputs, vec![])
+                .expect("Failed to init");
+
+        // Frames 6 and 7 are too few for segment 2, it goes after segment 1
+        assert_eq!(vm.free_frames(), 8);
+        assert_eq!(vm.translate(address(37)), Ok(10 * 4 + 5));
+
+        // 5 frames are free, but no 4 of them are contiguous
+        assert_eq!(vm.translate(address(48)), Err(VMError::MemoryFull));
+        assert_eq!(vm.free_frames(), 5);
+
+        // Segment 1 would overlap the end of segment 0
+        assert_eq!(
+            VirtualMemory::with_scheme(
+                geometry,
+                Scheme::PureSegmentation,
+                [("0", "16", "2"), ("1", "8", "4")]
+                    .iter()
+                    .map(|&(s, size, f)| STInput::new(s, size, f, &geometry))
+                    .collect::<VMResult<_>>()
+                    .expect("Failed to create STInput"),
+                vec![],
+            )
+            .map(|_| ()),
+            Err(VMError::InvalidFrame)
+        );
+    }
+
+    #[test]
+    fn fits_and_compaction() {
+        // 16 frames of 4 words, segments 0 and 1 leave holes of 3 and 5 frames
+        let geometry = Geometry::new(2, 2, 2, 16).expect("Failed to create Geometry");
+        let address =
+            |value| VirtualAddress::new(value, &geometry).expect("Failed to create VirtualAddress");
+        let before_fit = |fit| {
+            let st_inputs = [
+                ("0", "16", "2"),
+                ("1", "8", "9"),
+                ("2", "12", "-1"),
+                ("3", "16", "-5"),
+            ]
+            .iter()
+            .map(|&(s, size, f)| STInput::new(s, size, f, &geometry))
+            .collect::<VMResult<Vec<_>>>()
+            .expect("Failed to create STInput");
+            VirtualMemory::with_scheme(geometry, Scheme::PureSegmentation, st_inputs, vec![])
+                .expect("Failed to init")
+                .with_fit(fit)
+        };
+
+        // First fit puts segment 2 in frames 6 to 8, leaving room for segment 3
+        let mut vm = before_fit(Fit::First);
+        assert_eq!(vm.translate(address(37)), Ok(6 * 4 + 5));
+        assert_eq!(vm.translate(address(48)), Ok(11 * 4));
+
+        // Worst fit puts it in frames 11 to 13, splitting the free frames
+        let mut vm = before_fit(Fit::Worst);
+        assert_eq!(vm.fit(), Fit::Worst);
+        vm.write_word(address(37), 7).expect("Failed to write word");
+        assert_eq!(vm.segment(2), Ok((12, 11 * 4)));
+        assert_eq!(vm.translate(address(48)), Err(VMError::MemoryFull));
+        assert_eq!(
+            vm.fragmentation(),
+            FragmentationReport {
+                largest_hole: 3,
+                hole_count: 2,
+                total_free: 5,
+            }
+        );
+
+        // Segments 1 and 2 slide down over frames 6 to 8
+        assert_eq!(vm.compact(), Ok(5));
+        assert_eq!(vm.segment(1), Ok((8, 6 * 4)));
+        assert_eq!(vm.segment(2), Ok((12, 8 * 4)));
+        assert_eq!(vm.read_word(address(37)), Ok(7));
+        assert_eq!(vm.fragmentation().hole_count, 1);
+        assert_eq!(vm.translate(address(48)), Ok(11 * 4));
+        assert_eq!(vm.compact(), Ok(0));
+
+        assert_eq!(before().compact(), Err(VMError::UnsupportedScheme));
+    }
+
+    #[test]
+    fn next_fit_compaction() {
+        // 16 frames of 4 words, segment 1 sits alone in frames 12 and 13
+        let geometry = Geometry::new(2, 2, 2, 16).expect("Failed to create Geometry");
+        let address =
+            |value| VirtualAddress::new(value, &geometry).expect("Failed to create VirtualAddress");
+        let st_inputs = [
+            ("0", "16", "2"),
+            ("1", "8", "12"),
+            ("2", "12", "-1"),
+            ("3", "16", "-5"),
+        ]
+        .iter()
+        .map(|&(s, size, f)| STInput::new(s, size, f, &geometry))
+        .collect::<VMResult<Vec<_>>>()
+        .expect("Failed to create STInput");
+        let mut vm =
+            VirtualMemory::with_scheme(geometry, Scheme::PureSegmentation, st_inputs, vec![])
+                .expect("Failed to init")
+                .with_fit(Fit::Next);
+
+        assert_eq!(vm.translate(address(37)), Ok(6 * 4 + 5));
+        assert_eq!(vm.allocator.cursor(), 9);
+
+        // Segment 1 slides down to frame 9, where the cursor pointed
+        assert_eq!(vm.compact(), Ok(2));
+        assert_eq!(vm.segment(1), Ok((8, 9 * 4)));
+        assert_eq!(vm.allocator.cursor(), 0);
+        assert_eq!(vm.fit(), Fit::Next);
+        assert_eq!(vm.translate(address(48)), Ok(11 * 4));
+    }
+
+    #[test]
+    fn pure_paging() {
+        let mut vm = before_scheme(Scheme::PurePaging);
+        let geometry = Geometry::default();
+        let address =
+            |value| VirtualAddress::new(value, &geometry).expect("Failed to create VirtualAddress");
+
+        // Page 9/1 is page 4609, so the table takes 10 frames
+        assert_eq!(
+            vm.address_spaces[0].segment_table,
+            (0..10).collect::<Vec<_>>()
+        );
+        assert_eq!(vm.physical_memory[9].kind, FrameKind::PageDirectory);
+        assert_eq!(vm.physical_memory.get_word_by_offset(8, 0), 10);
+
+        assert_eq!(vm.translate(address(2_097_162)), Ok(10 * 512 + 10));
+        assert_eq!(vm.translate(address(2_097_674)), Ok(11 * 512 + 10));
+        assert_eq!(vm.stats().page_faults, 1);
+        assert_eq!(
+            vm.translate(address(2_098_176)),
+            Err(VMError::MemoryNotInitialized)
+        );
+        // Page 5120 is past the end of the table
+        assert_eq!(
+            vm.translate(address(2_621_440)),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+        assert_eq!(vm.segment(8), Err(VMError::UnsupportedScheme));
+
+        let process = vm
+            .create_process(
+                vec![],
+                vec![PTInput::new("8", "0", "20", &geometry).expect("Failed to create PTInput")],
+            )
+            .expect("Failed to create process");
+        assert_eq!(vm.address_spaces[process].segment_table.len(), 9);
+        assert_eq!(
+            vm.translate_for(process, address(2_097_162)),
+            Ok(20 * 512 + 10)
+        );
+        assert_eq!(
+            vm.translate(address(2_359_296)),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+
+        assert_eq!(
+            VirtualMemory::with_scheme(
+                geometry,
+                Scheme::PurePaging,
+                vec![],
+                vec![PTInput::new("8", "0", "2", &geometry).expect("Failed to create PTInput")],
+            )
+            .map(|_| ()),
+            Err(VMError::InvalidFrame)
+        );
+    }
+
+    #[test]
+    fn pure_paging_shared_frames() {
+        let geometry = Geometry::default();
+        let with_pages = |inputs: &[(&str, &str, &str)]| {
+            let pt_inputs = inputs
+                .iter()
+                .map(|&(s, p, f)| PTInput::new(s, p, f, &geometry))
+                .collect::<VMResult<Vec<_>>>()
+                .expect("Failed to create PTInput");
+            VirtualMemory::with_scheme(geometry, Scheme::PurePaging, vec![], pt_inputs)
+        };
+
+        assert_eq!(
+            with_pages(&[("6", "0", "20"), ("6", "1", "20")]).map(|_| ()),
+            Err(VMError::InvalidFrame)
+        );
+        assert_eq!(
+            with_pages(&[("6", "1", "-9"), ("6", "2", "-9")]).map(|_| ()),
+            Err(VMError::InvalidFrame)
+        );
+
+        // Another process's page table is taken as well as its pages
+        let mut vm = with_pages(&[("6", "0", "20"), ("6", "1", "-9")]).expect("Failed to init");
+        for frame in ["20", "-9", "2"] {
+            assert_eq!(
+                vm.create_process(
+                    vec![],
+                    vec![
+                        PTInput::new("6", "0", frame, &geometry).expect("Failed to create PTInput")
+                    ]
+                ),
+                Err(VMError::InvalidFrame)
+            );
+        }
+    }
+
+    #[test]
+    fn inverted() {
+        let geometry = Geometry::default();
+        let address =
+            |value| VirtualAddress::new(value, &geometry).expect("Failed to create VirtualAddress");
+        let pt_inputs = |inputs: &[(&str, &str, &str)]| {
+            inputs
+                .iter()
+                .map(|&(s, p, f)| PTInput::new(s, p, f, &geometry))
+                .collect::<VMResult<Vec<_>>>()
+                .expect("Failed to create PTInput")
+        };
+        let mut vm = VirtualMemory::with_scheme(
+            geometry,
+            Scheme::Inverted,
+            vec![],
+            pt_inputs(&[("8", "0", "10"), ("8", "1", "-20"), ("9", "0", "13")]),
+        )
+        .expect("Failed to init");
+
+        // 1024 anchors and 1024 entries of 3 words take the first 8 frames
+        assert_eq!(vm.physical_memory[7].kind, FrameKind::InvertedTable);
+        assert!(vm.physical_memory[8].is_free());
+
+        assert_eq!(vm.translate(address(2_097_162)), Ok(10 * 512 + 10));
+        assert_eq!(vm.translate(address(2_097_674)), Ok(8 * 512 + 10));
+        assert_eq!(vm.stats().page_faults, 1);
+        assert_eq!(
+            vm.translate(address(2_098_176)),
+            Err(VMError::MemoryNotInitialized)
+        );
+
+        // An evicted page comes back from the block it was written to
+        vm.write_word(address(2_359_306), 7)
+            .expect("Failed to write word");
+        vm.evict(13).expect("Failed to evict");
+        assert_eq!(vm.read_word(address(2_359_306)), Ok(7));
+        assert_eq!(vm.stats().page_faults, 2);
+
+        // Other processes map the same page numbers to their own frames
+        let process = vm
+            .create_process(vec![], pt_inputs(&[("8", "0", "20")]))
+            .expect("Failed to create process");
+        assert_eq!(
+            vm.translate_for(process, address(2_097_162)),
+            Ok(20 * 512 + 10)
+        );
+        assert_eq!(vm.translate_for(0, address(2_097_162)), Ok(10 * 512 + 10));
+
+        let stats = vm.inverted_stats().expect("Missing inverted stats");
+        assert_eq!(stats.table_frames, 8);
+        assert_eq!(stats.lookups, 7);
+        assert!(stats.average_probe_length() > 0.0);
+        assert_eq!(vm.fork(), Err(VMError::UnsupportedScheme));
+        assert_eq!(
+            VirtualMemory::with_scheme(
+                geometry,
+                Scheme::Inverted,
+                vec![],
+                pt_inputs(&[("8", "0", "2")])
+            )
+            .map(|_| ()),
+            Err(VMError::InvalidFrame)
+        );
+    }
+
+    #[test]
+    fn inverted_shared_frames() {
+        let geometry = Geometry::default();
+        let with_pages = |inputs: &[(&str, &str, &str)]| {
+            let pt_inputs = inputs
+                .iter()
+                .map(|&(s, p, f)| PTInput::new(s, p, f, &geometry))
+                .collect::<VMResult<Vec<_>>>()
+                .expect("Failed to create PTInput");
+            VirtualMemory::with_scheme(geometry, Scheme::Inverted, vec![], pt_inputs)
+        };
+
+        // A second page would overwrite the first one's entry in the table
+        assert_eq!(
+            with_pages(&[("6", "0", "10"), ("6", "1", "10")]).map(|_| ()),
+            Err(VMError::InvalidFrame)
+        );
+        assert_eq!(
+            with_pages(&[("6", "1", "-9"), ("6", "2", "-9")]).map(|_| ()),
+            Err(VMError::InvalidFrame)
+        );
+
+        // So would a page of another process
+        let mut vm = with_pages(&[("6", "0", "10"), ("6", "1", "-9")]).expect("Failed to init");
+        for frame in ["10", "-9"] {
+            assert_eq!(
+                vm.create_process(
+                    vec![],
+                    vec![
+                        PTInput::new("6", "0", frame, &geometry).expect("Failed to create PTInput")
+                    ]
+                ),
+                Err(VMError::InvalidFrame)
+            );
+        }
+    }
+
+    #[test]
+    fn resize_segment() {
+        let mut vm = before()
+            .with_tlb(TlbConfig {
+                entries: 4,
+                associativity: 4,
+                replacement: TlbReplacement::Lru,
+            })
+            .expect("Failed to create TLB");
+        let address = VirtualAddress::new(2_097_664, &Geometry::default())
+            .expect("Failed to create VirtualAddress");
+        assert_eq!(vm.translate(address), Ok(2 * 512));
+
+        // Page 1 of segment 8 goes away along with its frame and home block
+        vm.resize_segment(8, 512).expect("Failed to shrink segment");
+        assert_eq!(vm.segment_size(8), Ok(512));
+        assert_eq!(vm.physical_memory.get_word_by_offset(3, 1), 0);
+        assert!(vm.physical_memory[2].is_free());
+        assert!(!vm.disk.is_used(20));
+        assert_eq!(
+            vm.translate(address),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+
+        // Growing back maps a zeroed page
+        vm.write_word(
+            VirtualAddress::new(2_097_152, &Geometry::default())
+                .expect("Failed to create VirtualAddress"),
+            7,
+        )
+        .expect("Failed to write word");
+        vm.resize_segment(8, 1024).expect("Failed to grow segment");
+        assert_eq!(vm.read_word(address), Ok(0));
+        assert_eq!(vm.translate(address), Ok(2 * 512));
+        assert_eq!(vm.physical_memory.get_word_by_offset(10, 0), 7);
+
+        assert_eq!(vm.resize_segment(5, 10), Err(VMError::SegmentNotFound));
+        assert_eq!(
+            vm.resize_segment(8, 262_145),
+            Err(VMError::InvalidSegmentSize)
+        );
+    }
+
+    #[test]
+    fn resize_segment_page_table_on_disk() {
+        let mut vm = before();
+
+        vm.resize_segment(9, 512).expect("Failed to shrink segment");
+        assert_eq!(vm.disk[7][1], 0);
+        assert!(!vm.disk.is_used(25));
+
+        vm.resize_segment(9, 1024).expect("Failed to grow segment");
+        assert_eq!(vm.disk[7][1], 2);
+        assert_eq!(
+            vm.physical_memory[2].owner,
+            Some(EntryLocation::Disk {
+                block: 7,
+                offset: 1
+            })
+        );
+    }
+
+    #[test]
+    fn destroy_segment() {
+        let mut vm = before().with_protection(vec![ProtectionInput::new(
+            "8",
+            "*",
+            "r--",
+            &Geometry::default(),
+        )
+        .expect("Failed to create ProtectionInput")]);
+
+        vm.destroy_segment(8).expect("Failed to destroy segment");
+        assert_eq!(vm.segment_size(8), Ok(0));
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8 * 2 + 1), 0);
+        assert!(vm.physical_memory[3].is_free());
+        assert!(vm.physical_memory[10].is_free());
+        assert!(!vm.disk.is_used(20));
+        assert_eq!(vm.permissions(8, 0), Permissions::ALL);
+        assert_eq!(
+            vm.translate(
+                VirtualAddress::new(2_097_152, &Geometry::default())
+                    .expect("Failed to create VirtualAddress")
+            ),
+            Err(VMError::VirtualAddressOutOfBounds)
+        );
+        assert_eq!(vm.destroy_segment(8), Err(VMError::SegmentNotFound));
+
+        vm.destroy_segment(9).expect("Failed to destroy segment");
+        assert!(vm.physical_memory[13].is_free());
+        assert!(!vm.disk.is_used(7));
+        assert!(!vm.disk.is_used(25));
+
+        // Freed frames are handed out again
+        vm.create_segment(8, 512).expect("Failed to create segment");
+        assert_eq!(vm.physical_memory.get_word_by_offset(0, 8 * 2 + 1), 2);
     }
 }
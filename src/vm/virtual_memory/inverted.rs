@@ -0,0 +1,135 @@
+//! Translation through the inverted page table every process shares.
+//!
+//! The table only holds resident pages. A page missing from it is loaded
+//! from the block recorded when it was evicted, or is not initialized.
+
+use crate::{
+    error::{VMError, VMResult},
+    io::pt_input::PTInput,
+    vm::{
+        frame::{Frame, FrameKind},
+        inverted::PageKey,
+        trace::PageIn,
+        virtual_address::VirtualAddress,
+    },
+};
+
+use super::{Address, ProcessId, VirtualMemory};
+
+impl VirtualMemory {
+    /// Enters the resident pages of `page_table_init` in the inverted table
+    /// for `process` and records the blocks of the others, returning the page
+    /// frames that became resident.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidFrame` if a page is placed in the table's frames,
+    ///   or in a frame or disk block another page already holds.
+    pub(super) fn load_inverted_table(
+        &mut self,
+        process: ProcessId,
+        page_table_init: Vec<PTInput>,
+    ) -> VMResult<Vec<usize>> {
+        let Some(inverted) = &mut self.inverted else {
+            return Err(VMError::UnsupportedScheme);
+        };
+
+        let mut resident = Vec::new();
+        for pt_input in page_table_init {
+            let key = (process, pt_input.segment, pt_input.page);
+
+            if pt_input.frame.is_positive() {
+                let frame_number = usize::try_from(pt_input.frame)?;
+                let frame = &mut self.physical_memory[frame_number];
+                // Table frames are taken too, and every chained frame is
+                if !frame.is_free() {
+                    return Err(VMError::InvalidFrame);
+                }
+                frame.refs = 1;
+                frame.kind = FrameKind::Page;
+                inverted.insert(&mut self.physical_memory, frame_number, key);
+                resident.push(frame_number);
+            } else {
+                let block = usize::try_from(pt_input.frame.abs())?;
+                if self.disk.is_used(block) {
+                    return Err(VMError::InvalidFrame);
+                }
+                self.disk.claim(block);
+                inverted.swap_out(key, block);
+            }
+        }
+
+        Ok(resident)
+    }
+
+    /// Looks up `virtual_address` in the TLB, then in the inverted table.
+    pub(super) fn walk_inverted(&mut self, virtual_address: VirtualAddress) -> VMResult<Address> {
+        let VirtualAddress { s, p, w, .. } = virtual_address;
+
+        let page_frame = if let Some(frame) = self.cached_frame(virtual_address) {
+            frame
+        } else {
+            let frame = self.inverted_frame((self.active, s, p))?;
+            if let Some(tlb) = &mut self.tlb {
+                tlb.insert(s, p, frame);
+            }
+            frame
+        };
+
+        Ok(u32::try_from(
+            page_frame * self.geometry.page_size() + usize::from(w),
+        )?)
+    }
+
+    /// Frame holding page `key`, loading it from disk or mapping a zeroed one
+    /// with demand zero if it is not resident.
+    fn inverted_frame(&mut self, key: PageKey) -> VMResult<usize> {
+        let Some(inverted) = &mut self.inverted else {
+            return Err(VMError::UnsupportedScheme);
+        };
+
+        if let Some(frame) = inverted.lookup(&self.physical_memory, key) {
+            self.physical_memory[frame].referenced = true;
+            self.policy.accessed(frame);
+            return Ok(frame);
+        }
+
+        let block = inverted.swapped_block(key);
+        if block.is_none() && !self.demand_zero {
+            return Err(VMError::MemoryNotInitialized);
+        }
+
+        let frame = self.allocate_page(&[])?;
+        let page_size = self.geometry.page_size();
+        self.physical_memory[frame] = Frame {
+            refs: 1,
+            kind: FrameKind::Page,
+            referenced: true,
+            block,
+            data: block.map_or_else(|| vec![0; page_size], |block| self.disk[block].to_vec()),
+            ..Frame::new(page_size)
+        };
+
+        match block {
+            Some(_) => {
+                self.stats.disk_reads += 1;
+                self.stats.page_faults += 1;
+            }
+            None => self.stats.zero_fills += 1,
+        }
+        if let Some(page_ins) = &mut self.page_ins {
+            page_ins.push(PageIn {
+                kind: FrameKind::Page,
+                block,
+                frame,
+            });
+        }
+
+        if let Some(inverted) = &mut self.inverted {
+            inverted.swap_in(key);
+            inverted.insert(&mut self.physical_memory, frame, key);
+        }
+        self.policy.loaded(frame);
+
+        Ok(frame)
+    }
+}
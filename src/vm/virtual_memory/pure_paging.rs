@@ -0,0 +1,109 @@
+//! Translation through a single page table per process, without segments.
+//!
+//! The table takes as many frames as the highest page of the init file
+//! needs and never leaves memory, like a segment table. Pages past its end
+//! are out of bounds, the way a page table length register bounds them.
+
+use crate::{
+    error::{VMError, VMResult},
+    io::pt_input::PTInput,
+    vm::{
+        frame::{EntryLocation, FrameKind},
+        scheme::page_number,
+        virtual_address::VirtualAddress,
+    },
+};
+
+use super::{Address, ProcessId, VirtualMemory};
+
+impl VirtualMemory {
+    /// Frames taken by a page table long enough to map every page of
+    /// `page_table_init`, at least one.
+    pub(super) fn page_table_frames(&self, page_table_init: &[PTInput]) -> usize {
+        page_table_init
+            .iter()
+            .map(|pt_input| page_number(&self.geometry, pt_input.segment, pt_input.page) + 1)
+            .max()
+            .unwrap_or(1)
+            .div_ceil(self.geometry.page_size())
+    }
+
+    /// Physical address of the entry for `page_number` in the page table of
+    /// `process`, `None` past the end of the table.
+    pub(super) fn paging_entry(&self, process: ProcessId, page_number: usize) -> Option<usize> {
+        let address_space = &self.address_spaces[process];
+        (page_number < address_space.segment_table.len() * self.geometry.page_size())
+            .then(|| address_space.table_entry(page_number))
+    }
+
+    /// Fills the page table of `process` with the pages of
+    /// `page_table_init`, returning the page frames that became resident.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidFrame` if a page is placed in the table's frames,
+    ///   or in a frame or disk block another page already holds.
+    pub(super) fn load_page_table(
+        &mut self,
+        process: ProcessId,
+        page_table_init: Vec<PTInput>,
+    ) -> VMResult<Vec<usize>> {
+        let mut resident = Vec::new();
+
+        for pt_input in page_table_init {
+            let entry = self
+                .paging_entry(
+                    process,
+                    page_number(&self.geometry, pt_input.segment, pt_input.page),
+                )
+                .ok_or(VMError::InvalidPage)?;
+            self.set_entry(EntryLocation::Memory(entry), i32::from(pt_input.frame));
+
+            if pt_input.frame.is_positive() {
+                let frame_number = usize::try_from(pt_input.frame)?;
+                let frame = &mut self.physical_memory[frame_number];
+                if !frame.is_free() || frame.owner.is_some() {
+                    return Err(VMError::InvalidFrame);
+                }
+                frame.refs = 1;
+                frame.kind = FrameKind::Page;
+                frame.owner = Some(EntryLocation::Memory(entry));
+                resident.push(frame_number);
+            } else {
+                let block = usize::try_from(pt_input.frame.abs())?;
+                if self.disk.is_used(block) {
+                    return Err(VMError::InvalidFrame);
+                }
+                self.disk.claim(block);
+            }
+        }
+
+        Ok(resident)
+    }
+
+    /// Looks up `virtual_address` in the TLB, then in the page table of the
+    /// active process.
+    pub(super) fn walk_pure_paging(
+        &mut self,
+        virtual_address: VirtualAddress,
+    ) -> VMResult<Address> {
+        let VirtualAddress { s, p, w, .. } = virtual_address;
+
+        let page_frame = if let Some(frame) = self.cached_frame(virtual_address) {
+            frame
+        } else {
+            let entry = self
+                .paging_entry(self.active, page_number(&self.geometry, s, p))
+                .ok_or(VMError::VirtualAddressOutOfBounds)?;
+            let frame = self.get_frame(entry, FrameKind::Page, &[])?;
+
+            if let Some(tlb) = &mut self.tlb {
+                tlb.insert(s, p, frame);
+            }
+            frame
+        };
+
+        Ok(u32::try_from(
+            page_frame * self.geometry.page_size() + usize::from(w),
+        )?)
+    }
+}
@@ -0,0 +1,242 @@
+//! Translation through segment tables whose entries hold a limit and a base
+//! address, with every segment placed whole in contiguous frames.
+//!
+//! Segments are never split into pages. A segment on disk is loaded into a
+//! run of free frames long enough to hold it, picked by the contiguous
+//! allocator's fit, which can fail while enough frames are free in total
+//! until memory is compacted. The TLB is not consulted, the segment table
+//! entry is all there is to look up.
+
+use std::ops::Range;
+
+use crate::{
+    constants::{SEGMENT_BASE_OFFSET, SEGMENT_SIZE_OFFSET},
+    error::{VMError, VMResult},
+    io::st_input::STInput,
+    vm::{
+        allocator::ContiguousAllocator,
+        frame::{EntryLocation, Frame, FrameKind},
+        physical_memory::PhysicalMemoryTrait,
+        scheme::Scheme,
+        trace::PageIn,
+        virtual_address::VirtualAddress,
+    },
+};
+
+use super::{Address, ProcessId, VirtualMemory};
+
+impl VirtualMemory {
+    /// Frames a segment of `size` words takes.
+    const fn segment_frame_count(&self, size: usize) -> usize {
+        size.div_ceil(self.geometry.page_size())
+    }
+
+    /// Frames or disk blocks the segment of `st_input` starts at, depending
+    /// on the sign of its frame.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidFrame` if the segment runs past the last frame.
+    pub(super) fn segment_frames(&self, st_input: STInput) -> VMResult<Range<usize>> {
+        let first = usize::try_from(st_input.frame.abs())?;
+        let frames = first..first + self.segment_frame_count(usize::try_from(st_input.size)?);
+
+        if frames.end > self.geometry.frame_count() {
+            return Err(VMError::InvalidFrame);
+        }
+        Ok(frames)
+    }
+
+    /// Fills the segment table of `process` with the limit and base of every
+    /// segment of `segment_table_init`. Segments stay pinned, so no frame is
+    /// returned for the replacement policy.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidFrame` if a segment overlaps a segment table or
+    ///   another segment in memory or on disk, or runs past the last frame or
+    ///   block.
+    pub(super) fn load_segments(
+        &mut self,
+        process: ProcessId,
+        segment_table_init: Vec<STInput>,
+    ) -> VMResult<Vec<usize>> {
+        let page_size = self.geometry.page_size();
+
+        for st_input in segment_table_init {
+            let address_space = &self.address_spaces[process];
+            let limit_entry = address_space.segment_entry(st_input.segment, SEGMENT_SIZE_OFFSET);
+            let base_entry = address_space.segment_entry(st_input.segment, SEGMENT_BASE_OFFSET);
+            self.set_word(limit_entry, i32::try_from(st_input.size)?);
+
+            let frames = self.segment_frames(st_input)?;
+            if st_input.frame.is_positive() {
+                if self.physical_memory[frames.clone()]
+                    .iter()
+                    .any(|frame| !frame.is_free() && frame.kind != FrameKind::Page)
+                {
+                    return Err(VMError::InvalidFrame);
+                }
+
+                self.set_word(base_entry, i32::try_from(frames.start * page_size)?);
+                for frame in &mut self.physical_memory[frames] {
+                    frame.refs = 1;
+                    frame.kind = FrameKind::Segment;
+                    frame.owner = Some(EntryLocation::Memory(base_entry));
+                }
+            } else {
+                if frames.clone().any(|block| self.disk.is_used(block)) {
+                    return Err(VMError::InvalidFrame);
+                }
+
+                self.set_word(base_entry, i32::from(st_input.frame));
+                for block in frames {
+                    self.disk.claim(block);
+                }
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
+    /// Checks `virtual_address` against the limit of its segment in the
+    /// active process and adds it to the segment's base, loading the segment
+    /// from disk or placing a zeroed one with demand zero if it has no base.
+    pub(super) fn walk_pure_segmentation(
+        &mut self,
+        virtual_address: VirtualAddress,
+    ) -> VMResult<Address> {
+        let VirtualAddress { s, pw, .. } = virtual_address;
+        let address_space = &self.address_spaces[self.active];
+        let limit = self
+            .physical_memory
+            .get_word_by_address(address_space.segment_entry(s, SEGMENT_SIZE_OFFSET));
+        let base_entry = address_space.segment_entry(s, SEGMENT_BASE_OFFSET);
+
+        if pw >= u32::try_from(limit)? {
+            return Err(VMError::VirtualAddressOutOfBounds);
+        }
+
+        let base = match self.physical_memory.get_word_by_address(base_entry) {
+            block if block < 0 => {
+                self.place_segment(base_entry, Some(usize::try_from(block.abs())?))?
+            }
+            0 if self.demand_zero => self.place_segment(base_entry, None)?,
+            0 => return Err(VMError::MemoryNotInitialized),
+            base => usize::try_from(base)?,
+        };
+
+        let address = base + usize::try_from(pw)?;
+        self.physical_memory[address / self.geometry.page_size()].referenced = true;
+
+        Ok(u32::try_from(address)?)
+    }
+
+    /// Places the segment whose base is at `base_entry` in a run of free
+    /// frames long enough for its limit, copying it from the blocks starting
+    /// at `block` or zeroing it. Returns the new base.
+    ///
+    /// # Errors
+    /// - `VMError::MemoryFull` if no run of free frames is long enough.
+    fn place_segment(&mut self, base_entry: usize, block: Option<usize>) -> VMResult<usize> {
+        let page_size = self.geometry.page_size();
+        let limit = self
+            .physical_memory
+            .get_word_by_address(base_entry - SEGMENT_BASE_OFFSET + SEGMENT_SIZE_OFFSET);
+        let count = self.segment_frame_count(usize::try_from(limit)?);
+        let first = self
+            .allocator
+            .allocate(&self.physical_memory, count)
+            .ok_or(VMError::MemoryFull)?;
+
+        for (i, frame) in (first..first + count).enumerate() {
+            let block = block.map(|block| block + i);
+            self.physical_memory[frame] = Frame {
+                refs: 1,
+                kind: FrameKind::Segment,
+                owner: Some(EntryLocation::Memory(base_entry)),
+                block,
+                data: block.map_or_else(|| vec![0; page_size], |block| self.disk[block].to_vec()),
+                ..Frame::new(page_size)
+            };
+        }
+
+        self.stats.frames_allocated += u64::try_from(count)?;
+        match block {
+            Some(_) => {
+                self.stats.page_faults += 1;
+                self.stats.disk_reads += u64::try_from(count)?;
+            }
+            None => self.stats.zero_fills += 1,
+        }
+        if let Some(page_ins) = &mut self.page_ins {
+            page_ins.push(PageIn {
+                kind: FrameKind::Segment,
+                block,
+                frame: first,
+            });
+        }
+
+        let base = first * page_size;
+        self.set_word(base_entry, i32::try_from(base)?);
+
+        Ok(base)
+    }
+
+    /// Slides every resident segment down over the free frames below it,
+    /// rewriting its base, so the free frames end up in as few holes as
+    /// possible. Segment tables stay where they are, segments only close up
+    /// to the nearest one below them. Returns the number of frames moved.
+    ///
+    /// # Errors
+    /// - `VMError::UnsupportedScheme` without pure segmentation.
+    pub fn compact(&mut self) -> VMResult<usize> {
+        if self.scheme != Scheme::PureSegmentation {
+            return Err(VMError::UnsupportedScheme);
+        }
+
+        let page_size = self.geometry.page_size();
+        let mut moved = 0;
+        // Lowest frame the next segment can move to
+        let mut target = 1;
+        let mut frame = 1;
+
+        while frame < self.physical_memory.len() {
+            let current = &self.physical_memory[frame];
+            if current.is_free() {
+                frame += 1;
+                continue;
+            }
+            if current.kind != FrameKind::Segment {
+                frame += 1;
+                target = frame;
+                continue;
+            }
+
+            // Every frame of a segment is owned by its base
+            let owner = current.owner;
+            let count = self.physical_memory[frame..]
+                .iter()
+                .take_while(|next| next.kind == FrameKind::Segment && next.owner == owner)
+                .count();
+
+            if target < frame {
+                // Frames from target up to the segment are free, so swapping
+                // upwards never overwrites a frame still to be moved
+                for i in 0..count {
+                    self.physical_memory.swap(target + i, frame + i);
+                }
+                if let Some(EntryLocation::Memory(base_entry)) = owner {
+                    self.set_word(base_entry, i32::try_from(target * page_size)?);
+                }
+                moved += count;
+            }
+
+            target += count;
+            frame += count;
+        }
+
+        // The next fit cursor may now point into a moved segment
+        self.allocator = ContiguousAllocator::new(self.allocator.fit());
+
+        Ok(moved)
+    }
+}
@@ -0,0 +1,324 @@
+//! Translation through N-level radix page tables over a flat page number.
+//!
+//! Each process's single segment table frame holds the root table. Lower
+//! levels are page tables like any other: they are evicted, loaded back from
+//! disk and counted as page table faults.
+
+use crate::{
+    error::{VMError, VMResult},
+    io::pt_input::PTInput,
+    vm::{
+        frame::{EntryLocation, Frame, FrameKind},
+        scheme::radix_index,
+        virtual_address::VirtualAddress,
+    },
+};
+
+use super::{Address, ProcessId, VirtualMemory};
+
+impl VirtualMemory {
+    /// Builds the radix tables of `process` mapping every page of
+    /// `page_table_init`, returning the table and page frames that became
+    /// resident.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidFrame` if a page is placed in a frame or disk block
+    ///   a table or another page already holds.
+    pub(super) fn load_radix_tables(
+        &mut self,
+        process: ProcessId,
+        levels: usize,
+        page_table_init: Vec<PTInput>,
+    ) -> VMResult<Vec<usize>> {
+        // Claim every page first so the tables are allocated around them
+        for pt_input in &page_table_init {
+            if pt_input.frame.is_positive() {
+                let frame = &mut self.physical_memory[usize::try_from(pt_input.frame)?];
+                if !frame.is_free() {
+                    return Err(VMError::InvalidFrame);
+                }
+                frame.refs = 1;
+            }
+        }
+
+        let mut resident = Vec::new();
+        for pt_input in page_table_init {
+            let entry = self.radix_entry(
+                process,
+                levels,
+                pt_input.segment,
+                pt_input.page,
+                &mut resident,
+            )?;
+            self.set_entry(entry, i32::from(pt_input.frame));
+
+            if pt_input.frame.is_positive() {
+                let frame_number = usize::try_from(pt_input.frame)?;
+                let frame = &mut self.physical_memory[frame_number];
+                frame.refs = 1;
+                frame.kind = FrameKind::Page;
+                frame.owner = Some(entry);
+                resident.push(frame_number);
+            } else {
+                let block = usize::try_from(pt_input.frame.abs())?;
+                if self.disk.is_used(block) {
+                    return Err(VMError::InvalidFrame);
+                }
+                self.disk.claim(block);
+            }
+        }
+
+        Ok(resident)
+    }
+
+    /// Location of the last level entry for page `p` of segment `s` in
+    /// `process`, allocating the tables on the way that do not exist yet and
+    /// adding them to `resident`.
+    fn radix_entry(
+        &mut self,
+        process: ProcessId,
+        levels: usize,
+        s: u16,
+        p: u16,
+        resident: &mut Vec<usize>,
+    ) -> VMResult<EntryLocation> {
+        let page_size = self.geometry.page_size();
+        let root = self.address_spaces[process].segment_table[0];
+        let mut entry =
+            EntryLocation::Memory(root * page_size + radix_index(&self.geometry, levels, s, p, 0));
+
+        for level in 1..levels {
+            let mut table = self.get_entry(entry);
+            if table == 0 {
+                let pinned = match entry {
+                    EntryLocation::Memory(address) => vec![address / page_size],
+                    EntryLocation::Disk { .. } => vec![],
+                };
+                let frame = self.allocate_page(&pinned)?;
+                self.physical_memory[frame] = Frame {
+                    refs: 1,
+                    kind: FrameKind::PageTable,
+                    owner: Some(entry),
+                    ..Frame::new(page_size)
+                };
+                table = i32::try_from(frame)?;
+                self.set_entry(entry, table);
+                resident.push(frame);
+            }
+
+            entry = self.entry_location(table, radix_index(&self.geometry, levels, s, p, level))?;
+        }
+
+        Ok(entry)
+    }
+
+    /// Looks up `virtual_address` in the TLB, then walks the radix tables of
+    /// the active process from the root down, paging in each level as needed.
+    pub(super) fn walk_radix(
+        &mut self,
+        virtual_address: VirtualAddress,
+        levels: usize,
+    ) -> VMResult<Address> {
+        let VirtualAddress { s, p, w, .. } = virtual_address;
+        let page_size = self.geometry.page_size();
+
+        let page_frame = if let Some(frame) = self.cached_frame(virtual_address) {
+            frame
+        } else {
+            let mut frame = self.address_spaces[self.active].segment_table[0];
+            // Tables above the current level stay put while it is loaded
+            let mut pinned = Vec::with_capacity(levels);
+            for level in 0..levels {
+                let kind = if level + 1 == levels {
+                    FrameKind::Page
+                } else {
+                    FrameKind::PageTable
+                };
+                pinned.push(frame);
+                frame = self.get_frame(
+                    frame * page_size + radix_index(&self.geometry, levels, s, p, level),
+                    kind,
+                    &pinned,
+                )?;
+            }
+
+            if let Some(tlb) = &mut self.tlb {
+                tlb.insert(s, p, frame);
+            }
+            frame
+        };
+
+        Ok(u32::try_from(page_frame * page_size + usize::from(w))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::VMError,
+        geometry::Geometry,
+        vm::{physical_memory::PhysicalMemoryTrait, scheme::Scheme},
+    };
+
+    use super::*;
+
+    fn before(levels: usize, pt_inputs: &[(&str, &str, &str)]) -> VirtualMemory {
+        let geometry = Geometry::default();
+        let pt_inputs = pt_inputs
+            .iter()
+            .map(|&(s, p, f)| PTInput::new(s, p, f, &geometry))
+            .collect::<VMResult<Vec<_>>>()
+            .expect("Failed to create PTInput");
+
+        VirtualMemory::with_scheme(geometry, Scheme::Radix { levels }, vec![], pt_inputs)
+            .expect("Failed to init")
+    }
+
+    fn address(virtual_address: u32) -> VirtualAddress {
+        VirtualAddress::new(virtual_address, &Geometry::default())
+            .expect("Failed to create VirtualAddress")
+    }
+
+    /// Entries read on the way from the root to the page, following
+    /// `indexes` through resident tables.
+    fn walk(vm: &VirtualMemory, indexes: &[usize]) -> Vec<i32> {
+        let mut frame = 0;
+        indexes
+            .iter()
+            .map(|&index| {
+                let entry = vm.physical_memory.get_word_by_offset(frame, index);
+                frame = usize::try_from(entry).unwrap_or(0);
+                entry
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shared_frames() {
+        let geometry = Geometry::default();
+        let with_pages = |pt_inputs: &[(&str, &str, &str)]| {
+            let pt_inputs = pt_inputs
+                .iter()
+                .map(|&(s, p, f)| PTInput::new(s, p, f, &geometry))
+                .collect::<VMResult<Vec<_>>>()
+                .expect("Failed to create PTInput");
+            VirtualMemory::with_scheme(geometry, Scheme::Radix { levels: 2 }, vec![], pt_inputs)
+                .map(|_| ())
+        };
+
+        // Two pages in one frame would alias, two on one block would free it
+        // for each other
+        assert_eq!(
+            with_pages(&[("6", "0", "7"), ("6", "1", "7")]),
+            Err(VMError::InvalidFrame)
+        );
+        assert_eq!(
+            with_pages(&[("6", "1", "-9"), ("6", "2", "-9")]),
+            Err(VMError::InvalidFrame)
+        );
+
+        // Pages of another process are taken too
+        let mut vm = before(2, &[("6", "0", "7"), ("6", "1", "-9")]);
+        for frame in ["7", "-9"] {
+            assert_eq!(
+                vm.create_process(
+                    vec![],
+                    vec![
+                        PTInput::new("6", "0", frame, &geometry).expect("Failed to create PTInput")
+                    ]
+                ),
+                Err(VMError::InvalidFrame)
+            );
+        }
+    }
+
+    #[test]
+    fn uneven_levels() {
+        // 18 page number bits over 4 levels are 5 bits for each of the lower
+        // three and 3 for the root
+        let mut vm = before(4, &[("9", "1", "13"), ("511", "511", "20")]);
+
+        // Page 9/1 is 4609, or 0, 4, 16 and 1
+        let tables = walk(&vm, &[0, 4, 16, 1]);
+        assert_eq!(tables[3], 13);
+        for &table in &tables[..3] {
+            let frame = usize::try_from(table).expect("Table is resident");
+            assert_eq!(vm.physical_memory[frame].kind, FrameKind::PageTable);
+        }
+        assert_eq!(vm.physical_memory[0].kind, FrameKind::PageDirectory);
+
+        // The last page fills every bit, but the root only has 8 entries
+        assert_eq!(walk(&vm, &[7, 31, 31, 31])[3], 20);
+        assert!((8..vm.geometry.page_size())
+            .all(|offset| vm.physical_memory.get_word_by_offset(0, offset) == 0));
+
+        assert_eq!(vm.translate(address(2_359_811)), Ok(13 * 512 + 3));
+        assert_eq!(vm.translate(address(134_217_216)), Ok(20 * 512));
+        assert_eq!(vm.stats().page_table_faults, 0);
+    }
+
+    #[test]
+    fn missing_table() {
+        // Segment 10 page 0 is 1, 16 and 0 with 6 bits per level, the root
+        // entry it shares with segment 8 leads to a table with no entry 16
+        let pt_inputs = [("8", "0", "10")];
+        let mut vm = before(3, &pt_inputs);
+        let middle = walk(&vm, &[1])[0];
+        assert_eq!(walk(&vm, &[1, 16]), vec![middle, 0]);
+        let free_frames = vm.free_frames();
+
+        assert_eq!(
+            vm.translate(address(2_621_440)),
+            Err(VMError::MemoryNotInitialized)
+        );
+        assert_eq!(vm.free_frames(), free_frames);
+
+        // With demand zero the last level table and the page are both filled
+        let mut vm = before(3, &pt_inputs).with_demand_zero(true);
+        let page = vm
+            .translate(address(2_621_440))
+            .expect("Failed to translate")
+            / 512;
+        let tables = walk(&vm, &[1, 16, 0]);
+        let last = usize::try_from(tables[1]).expect("Table is resident");
+        assert_eq!(vm.physical_memory[last].kind, FrameKind::PageTable);
+        assert_eq!(tables[2], i32::try_from(page).expect("Frame fits"));
+        assert_eq!(
+            vm.physical_memory[usize::try_from(page).expect("Frame fits")].kind,
+            FrameKind::Page
+        );
+        assert_eq!(vm.free_frames(), free_frames - 2);
+        assert_eq!(vm.stats().zero_fills, 2);
+    }
+
+    #[test]
+    fn middle_table_fault() {
+        // Page 9/0 is 1, 8 and 0 with 6 bits per level
+        let mut vm = before(3, &[("8", "0", "10"), ("9", "0", "13")]);
+        let tables = walk(&vm, &[1, 8, 0]);
+        let middle = usize::try_from(tables[0]).expect("Table is resident");
+        let last = usize::try_from(tables[1]).expect("Table is resident");
+
+        // Only the middle table goes to disk, the last one stays resident
+        vm.evict(middle).expect("Failed to evict");
+        assert!(vm.physical_memory.get_word_by_offset(0, 1) < 0);
+        assert!(matches!(
+            vm.physical_memory[last].owner,
+            Some(EntryLocation::Disk { offset: 8, .. })
+        ));
+
+        assert_eq!(vm.translate(address(2_359_306)), Ok(13 * 512 + 10));
+        assert_eq!(vm.stats().page_table_faults, 1);
+        assert_eq!(vm.stats().page_faults, 0);
+
+        // The reloaded middle table owns the last table again
+        let middle = usize::try_from(walk(&vm, &[1])[0]).expect("Table is resident");
+        assert_eq!(vm.physical_memory[middle].kind, FrameKind::PageTable);
+        assert_eq!(
+            vm.physical_memory[last].owner,
+            Some(EntryLocation::Memory(middle * 512 + 8))
+        );
+        assert_eq!(vm.translate(address(2_097_162)), Ok(10 * 512 + 10));
+        assert_eq!(vm.stats().page_table_faults, 1);
+    }
+}
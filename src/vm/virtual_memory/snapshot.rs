@@ -0,0 +1,1012 @@
+//! Line based text snapshots of a `VirtualMemory`.
+//!
+//! Each line starts with a keyword followed by space separated fields. Free
+//! frames with default flags, all-zero frame data and all-zero disk blocks
+//! are left out.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    io::{Read, Write},
+    path::PathBuf,
+    str::{FromStr, SplitWhitespace},
+};
+
+use crate::{
+    constants::{
+        INVERTED_NEXT_OFFSET, INVERTED_WORD_COUNT, SEGMENT_BASE_OFFSET, SEGMENT_PAGE_TABLE_OFFSET,
+        SEGMENT_SIZE_OFFSET, SEGMENT_WORD_COUNT,
+    },
+    error::{VMError, VMResult},
+    geometry::Geometry,
+    io::data::protection::Protection,
+    vm::{
+        address_space::AddressSpace,
+        allocator::{self, ContiguousAllocator},
+        frame::{EntryLocation, Frame, FrameKind},
+        inverted::{InvertedStats, InvertedTable},
+        physical_memory::PhysicalMemoryTrait,
+        protection::Permissions,
+        replacement,
+        scheme::{self, Scheme},
+        stats::Stats,
+        tlb::{Tlb, TlbConfig, TlbEntry, TlbReplacement, TlbStats},
+    },
+};
+
+use super::VirtualMemory;
+
+/// First word of every snapshot, followed by `VERSION`.
+const HEADER: &str = "vm-snapshot";
+const VERSION: u32 = 1;
+/// Last line of every snapshot, so truncated files are rejected.
+const END: &str = "end";
+/// Field standing for `None`.
+const NONE: &str = "-";
+
+/// Formats `values` as a list of fields, each preceded by a space.
+fn fields<T: Display>(values: impl IntoIterator<Item = T>) -> String {
+    values.into_iter().fold(String::new(), |mut fields, value| {
+        fields.push(' ');
+        fields.push_str(&value.to_string());
+        fields
+    })
+}
+
+const fn kind_name(kind: FrameKind) -> &'static str {
+    match kind {
+        FrameKind::SegmentTable => "st",
+        FrameKind::SegmentDirectory => "dir",
+        FrameKind::PageDirectory => "pdir",
+        FrameKind::InvertedTable => "ipt",
+        FrameKind::Segment => "seg",
+        FrameKind::PageTable => "pt",
+        FrameKind::Page => "page",
+    }
+}
+
+fn owner_name(owner: Option<EntryLocation>) -> String {
+    match owner {
+        None => NONE.to_string(),
+        Some(EntryLocation::Memory(address)) => format!("m{address}"),
+        Some(EntryLocation::Disk { block, offset }) => format!("d{block}:{offset}"),
+    }
+}
+
+/// Pulls the next field of a line and parses it.
+fn next<T: FromStr>(fields: &mut SplitWhitespace) -> VMResult<T> {
+    fields
+        .next()
+        .and_then(|field| field.parse().ok())
+        .ok_or(VMError::InvalidSnapshot)
+}
+
+/// Parses the next field as an index below `limit`.
+fn next_index(fields: &mut SplitWhitespace, limit: usize) -> VMResult<usize> {
+    let index = next(fields)?;
+    if index < limit {
+        Ok(index)
+    } else {
+        Err(VMError::InvalidSnapshot)
+    }
+}
+
+fn next_flag(fields: &mut SplitWhitespace) -> VMResult<bool> {
+    match next::<u8>(fields)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(VMError::InvalidSnapshot),
+    }
+}
+
+/// Parses the remaining fields as indexes below `limit`.
+fn rest_indexes(fields: SplitWhitespace, limit: usize) -> VMResult<Vec<usize>> {
+    fields
+        .map(|field| field.parse().ok().filter(|&index| index < limit))
+        .collect::<Option<Vec<usize>>>()
+        .ok_or(VMError::InvalidSnapshot)
+}
+
+fn next_protection(fields: &mut SplitWhitespace) -> VMResult<Permissions> {
+    fields
+        .next()
+        .and_then(|field| Protection::new(field).ok())
+        .map(|protection| protection.value())
+        .ok_or(VMError::InvalidSnapshot)
+}
+
+/// Parses a `tlb` line into everything but the entries.
+fn parse_tlb(fields: &mut SplitWhitespace) -> VMResult<(TlbConfig, TlbStats, u64)> {
+    let entries = next(fields)?;
+    let associativity = next(fields)?;
+    let replacement = match fields.next() {
+        Some("fifo") => TlbReplacement::Fifo,
+        Some("lru") => TlbReplacement::Lru,
+        _ => return Err(VMError::InvalidSnapshot),
+    };
+    let config = TlbConfig {
+        entries,
+        associativity,
+        replacement,
+    };
+    let stats = TlbStats {
+        hits: next(fields)?,
+        misses: next(fields)?,
+    };
+
+    Ok((config, stats, next(fields)?))
+}
+
+/// Parses a `stats` line, which has no other fields.
+fn parse_stats(fields: &mut SplitWhitespace) -> VMResult<Stats> {
+    let stats = Stats {
+        translations: next(fields)?,
+        page_table_faults: next(fields)?,
+        page_faults: next(fields)?,
+        out_of_bounds: next(fields)?,
+        not_initialized: next(fields)?,
+        frames_allocated: next(fields)?,
+        disk_reads: next(fields)?,
+        zero_fills: next(fields)?,
+    };
+
+    if fields.next().is_some() {
+        return Err(VMError::InvalidSnapshot);
+    }
+    Ok(stats)
+}
+
+fn parse_owner(field: Option<&str>, geometry: &Geometry) -> VMResult<Option<EntryLocation>> {
+    let field = field.ok_or(VMError::InvalidSnapshot)?;
+    if field == NONE {
+        return Ok(None);
+    }
+
+    let parse = |value: &str, limit: usize| {
+        value
+            .parse()
+            .ok()
+            .filter(|&value| value < limit)
+            .ok_or(VMError::InvalidSnapshot)
+    };
+
+    if let Some(address) = field.strip_prefix('m') {
+        Ok(Some(EntryLocation::Memory(parse(
+            address,
+            geometry.frame_count() * geometry.page_size(),
+        )?)))
+    } else if let Some((block, offset)) = field
+        .strip_prefix('d')
+        .and_then(|location| location.split_once(':'))
+    {
+        Ok(Some(EntryLocation::Disk {
+            block: parse(block, geometry.frame_count())?,
+            offset: parse(offset, geometry.page_size())?,
+        }))
+    } else {
+        Err(VMError::InvalidSnapshot)
+    }
+}
+
+/// Parses the fields of a `frame` line after the frame number.
+fn parse_frame(fields: &mut SplitWhitespace, geometry: &Geometry) -> VMResult<Frame> {
+    let refs = next(fields)?;
+    let kind = match fields.next() {
+        Some("st") => FrameKind::SegmentTable,
+        Some("dir") => FrameKind::SegmentDirectory,
+        Some("pdir") => FrameKind::PageDirectory,
+        Some("ipt") => FrameKind::InvertedTable,
+        Some("seg") => FrameKind::Segment,
+        Some("pt") => FrameKind::PageTable,
+        Some("page") => FrameKind::Page,
+        _ => return Err(VMError::InvalidSnapshot),
+    };
+    let owner = parse_owner(fields.next(), geometry)?;
+    let referenced = next_flag(fields)?;
+    let dirty = next_flag(fields)?;
+    let block = match fields.next() {
+        Some(NONE) => None,
+        field => Some(
+            field
+                .and_then(|field| field.parse().ok())
+                .filter(|&block| block < geometry.frame_count())
+                .ok_or(VMError::InvalidSnapshot)?,
+        ),
+    };
+
+    Ok(Frame {
+        refs,
+        kind,
+        owner,
+        referenced,
+        dirty,
+        block,
+        ..Frame::new(geometry.page_size())
+    })
+}
+
+/// Parses the remaining fields as exactly `len` words.
+fn rest_words(fields: SplitWhitespace, len: usize) -> VMResult<Vec<i32>> {
+    let words = fields
+        .map(str::parse)
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| VMError::InvalidSnapshot)?;
+
+    if words.len() == len {
+        Ok(words)
+    } else {
+        Err(VMError::InvalidSnapshot)
+    }
+}
+
+impl VirtualMemory {
+    /// Writes the complete state of the `VirtualMemory` as a versioned text
+    /// snapshot that `load_snapshot` turns back into an identical one.
+    pub fn save_snapshot(&self, writer: &mut impl Write) -> VMResult<()> {
+        let geometry = &self.geometry;
+        writeln!(writer, "{HEADER} {VERSION}")?;
+        writeln!(
+            writer,
+            "geometry {} {} {} {}",
+            geometry.segment_bits(),
+            geometry.page_bits(),
+            geometry.offset_bits(),
+            geometry.frame_count()
+        )?;
+        writeln!(writer, "active {}", self.active)?;
+        writeln!(
+            writer,
+            "policy {}{}",
+            self.policy.name(),
+            fields(self.policy.state())
+        )?;
+        if self.demand_zero {
+            writeln!(writer, "demand-zero")?;
+        }
+        if self.paged_segment_table {
+            writeln!(writer, "paged-segment-table")?;
+        }
+        if self.scheme != Scheme::Segmented {
+            writeln!(writer, "scheme {}", self.scheme)?;
+        }
+        if self.allocator != ContiguousAllocator::default() {
+            writeln!(
+                writer,
+                "fit {} {}",
+                self.allocator.fit().name(),
+                self.allocator.cursor()
+            )?;
+        }
+
+        let stats = self.stats;
+        writeln!(
+            writer,
+            "stats {} {} {} {} {} {} {} {}",
+            stats.translations,
+            stats.page_table_faults,
+            stats.page_faults,
+            stats.out_of_bounds,
+            stats.not_initialized,
+            stats.frames_allocated,
+            stats.disk_reads,
+            stats.zero_fills
+        )?;
+
+        self.save_tlb(writer)?;
+        self.save_address_spaces(writer)?;
+        self.save_inverted(writer)?;
+        self.save_memory(writer)?;
+
+        writeln!(writer, "{END}")?;
+
+        Ok(())
+    }
+
+    fn save_tlb(&self, writer: &mut impl Write) -> VMResult<()> {
+        let Some(tlb) = &self.tlb else {
+            return Ok(());
+        };
+
+        let config = tlb.config();
+        let replacement = match config.replacement {
+            TlbReplacement::Fifo => "fifo",
+            TlbReplacement::Lru => "lru",
+        };
+        writeln!(
+            writer,
+            "tlb {} {} {replacement} {} {} {}",
+            config.entries,
+            config.associativity,
+            tlb.stats().hits,
+            tlb.stats().misses,
+            tlb.time()
+        )?;
+
+        for entry in tlb.entries() {
+            writeln!(
+                writer,
+                "tlb-entry {} {} {} {} {}",
+                entry.s, entry.p, entry.frame, entry.loaded, entry.used
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn save_address_spaces(&self, writer: &mut impl Write) -> VMResult<()> {
+        for (process, address_space) in self.address_spaces.iter().enumerate() {
+            writeln!(writer, "process{}", fields(&address_space.segment_table))?;
+
+            for (s, permissions) in address_space.segment_permissions.iter().enumerate() {
+                if *permissions != Permissions::ALL {
+                    writeln!(writer, "segment-permission {process} {s} {permissions}")?;
+                }
+            }
+
+            let mut page_permissions: Vec<_> = address_space.page_permissions.iter().collect();
+            page_permissions.sort_unstable_by_key(|(key, _)| **key);
+            for ((s, p), permissions) in page_permissions {
+                writeln!(writer, "page-permission {process} {s} {p} {permissions}")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn save_inverted(&self, writer: &mut impl Write) -> VMResult<()> {
+        let Some(inverted) = &self.inverted else {
+            return Ok(());
+        };
+
+        let stats = inverted.stats();
+        writeln!(writer, "inverted {} {}", stats.lookups, stats.probes)?;
+
+        let mut swapped: Vec<_> = inverted.swapped().collect();
+        swapped.sort_unstable();
+        for ((process, s, p), block) in swapped {
+            writeln!(writer, "swapped {process} {s} {p} {block}")?;
+        }
+
+        Ok(())
+    }
+
+    fn save_memory(&self, writer: &mut impl Write) -> VMResult<()> {
+        let default_frame = Frame::new(self.geometry.page_size());
+
+        for (i, frame) in self.physical_memory.iter().enumerate() {
+            let flags = Frame {
+                data: default_frame.data.clone(),
+                ..frame.clone()
+            };
+            if flags != default_frame {
+                writeln!(
+                    writer,
+                    "frame {i} {} {} {} {} {} {}",
+                    frame.refs,
+                    kind_name(frame.kind),
+                    owner_name(frame.owner),
+                    u8::from(frame.referenced),
+                    u8::from(frame.dirty),
+                    frame
+                        .block
+                        .map_or_else(|| NONE.to_string(), |block| block.to_string())
+                )?;
+            }
+
+            if frame.data.iter().any(|&word| word != 0) {
+                writeln!(writer, "data {i}{}", fields(&frame.data))?;
+            }
+        }
+
+        writeln!(writer, "disk-used{}", fields(self.disk.used_blocks()))?;
+
+        for (i, block) in self.disk.blocks().enumerate() {
+            if block.iter().any(|&word| word != 0) {
+                writeln!(writer, "block {i}{}", fields(block))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a snapshot written by `save_snapshot`.
+    ///
+    /// # Errors
+    /// - `VMError::InvalidSnapshot` if the snapshot is malformed, truncated or
+    ///   was written by another version.
+    pub fn load_snapshot(reader: &mut impl Read) -> VMResult<Self> {
+        let mut data = String::new();
+        reader.read_to_string(&mut data)?;
+        let mut lines = data.lines();
+
+        if lines.next() != Some(format!("{HEADER} {VERSION}").as_str()) {
+            return Err(VMError::InvalidSnapshot);
+        }
+
+        let mut geometry_fields = lines
+            .next()
+            .and_then(|line| line.strip_prefix("geometry"))
+            .ok_or(VMError::InvalidSnapshot)?
+            .split_whitespace();
+        let geometry = Geometry::new(
+            next(&mut geometry_fields)?,
+            next(&mut geometry_fields)?,
+            next(&mut geometry_fields)?,
+            next(&mut geometry_fields)?,
+        )
+        .map_err(|_| VMError::InvalidSnapshot)?;
+
+        let mut virtual_memory = Self::with_geometry(geometry, vec![], vec![])?;
+        virtual_memory.physical_memory =
+            vec![Frame::new(geometry.page_size()); geometry.frame_count()];
+        virtual_memory.address_spaces.clear();
+
+        let mut tlb = None;
+        let mut tlb_entries = Vec::new();
+        let mut ended = false;
+        // Line number of each `data` and `block` line, by whether it is a
+        // block and its index
+        let mut word_lines = HashMap::new();
+
+        for (number, line) in lines.enumerate() {
+            let mut fields = line.split_whitespace();
+            if let (Some(keyword @ ("data" | "block")), Some(Ok(index))) =
+                (fields.next(), fields.next().map(str::parse::<usize>))
+            {
+                word_lines.insert((keyword == "block", index), number + 3);
+            }
+
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                _ if ended => return Err(VMError::InvalidSnapshot),
+                Some("tlb") => tlb = Some(parse_tlb(&mut fields)?),
+                Some("tlb-entry") => tlb_entries.push(TlbEntry {
+                    s: next(&mut fields)?,
+                    p: next(&mut fields)?,
+                    frame: next_index(&mut fields, geometry.frame_count())?,
+                    loaded: next(&mut fields)?,
+                    used: next(&mut fields)?,
+                }),
+                Some(END) => ended = true,
+                Some(keyword) => virtual_memory.load_line(keyword, fields)?,
+                None => return Err(VMError::InvalidSnapshot),
+            }
+        }
+
+        if !ended || virtual_memory.active >= virtual_memory.address_spaces.len() {
+            return Err(VMError::InvalidSnapshot);
+        }
+
+        virtual_memory.tlb = match tlb {
+            Some((config, stats, time)) => Some(
+                Tlb::restore(config, stats, time, tlb_entries)
+                    .map_err(|_| VMError::InvalidSnapshot)?,
+            ),
+            None if tlb_entries.is_empty() => None,
+            None => return Err(VMError::InvalidSnapshot),
+        };
+
+        if let Some(entry) = virtual_memory.invalid_entry() {
+            let page_size = geometry.page_size();
+            let (is_block, index, offset) = match entry {
+                EntryLocation::Memory(address) => (false, address / page_size, address % page_size),
+                EntryLocation::Disk { block, offset } => (true, block, offset),
+            };
+            // The file is filled in by `VMError::in_file`
+            return Err(VMError::Parse {
+                file: PathBuf::new(),
+                line: word_lines
+                    .get(&(is_block, index))
+                    .copied()
+                    .unwrap_or_default(),
+                token: offset + 3,
+                error: Box::new(VMError::InvalidFrame),
+            });
+        }
+        virtual_memory
+            .rebuild_sharers()
+            .map_err(|_| VMError::InvalidSnapshot)?;
+
+        Ok(virtual_memory)
+    }
+
+    /// Applies one snapshot line other than the header, TLB and end lines.
+    fn load_line(&mut self, keyword: &str, mut fields: SplitWhitespace) -> VMResult<()> {
+        let geometry = self.geometry;
+        let frame_count = geometry.frame_count();
+
+        match keyword {
+            "active" => self.active = next(&mut fields)?,
+            "policy" => {
+                let mut policy = fields
+                    .next()
+                    .and_then(replacement::from_name)
+                    .ok_or(VMError::InvalidSnapshot)?;
+                policy.restore(&rest_indexes(fields, frame_count)?);
+                self.policy = policy;
+            }
+            "demand-zero" => self.demand_zero = true,
+            "fit" => {
+                let fit = fields
+                    .next()
+                    .and_then(allocator::from_name)
+                    .ok_or(VMError::InvalidSnapshot)?;
+                self.allocator = ContiguousAllocator::restore(fit, next(&mut fields)?);
+            }
+            "paged-segment-table" if self.address_spaces.is_empty() => {
+                self.paged_segment_table = true;
+            }
+            "scheme" if self.address_spaces.is_empty() => {
+                let scheme = fields
+                    .next()
+                    .and_then(scheme::from_name)
+                    .ok_or(VMError::InvalidSnapshot)?;
+                scheme
+                    .validate(&geometry)
+                    .map_err(|_| VMError::InvalidSnapshot)?;
+                self.scheme = scheme;
+                if scheme == Scheme::Inverted {
+                    self.inverted = Some(InvertedTable::new(&geometry));
+                }
+            }
+            "inverted" | "swapped" => self.load_inverted_line(keyword, fields)?,
+            "stats" => self.stats = parse_stats(&mut fields)?,
+            "process" => {
+                let segment_table = rest_indexes(fields, frame_count)?;
+                // A pure paging page table is as long as its pages need
+                let valid = match self.scheme {
+                    Scheme::PurePaging => !segment_table.is_empty(),
+                    _ => segment_table.len() == self.segment_table_layout(&[]).0,
+                };
+                if !valid {
+                    return Err(VMError::InvalidSnapshot);
+                }
+                self.address_spaces
+                    .push(AddressSpace::new(segment_table, &geometry));
+            }
+            "segment-permission" => {
+                let process = next_index(&mut fields, self.address_spaces.len())?;
+                let s = next_index(&mut fields, geometry.segment_count())?;
+                self.address_spaces[process].segment_permissions[s] = next_protection(&mut fields)?;
+            }
+            "page-permission" => {
+                let process = next_index(&mut fields, self.address_spaces.len())?;
+                let s = next_index(&mut fields, geometry.segment_count())?;
+                let p = next_index(&mut fields, geometry.pages_per_segment())?;
+                self.address_spaces[process].page_permissions.insert(
+                    (u16::try_from(s)?, u16::try_from(p)?),
+                    next_protection(&mut fields)?,
+                );
+            }
+            "frame" => {
+                let i = next_index(&mut fields, frame_count)?;
+                self.physical_memory[i] = Frame {
+                    data: std::mem::take(&mut self.physical_memory[i].data),
+                    ..parse_frame(&mut fields, &geometry)?
+                };
+            }
+            "data" => {
+                let i = next_index(&mut fields, frame_count)?;
+                self.physical_memory[i].data = rest_words(fields, geometry.page_size())?;
+            }
+            "disk-used" => {
+                for block in rest_indexes(fields, frame_count)? {
+                    self.disk.claim(block);
+                }
+            }
+            "block" => {
+                let i = next_index(&mut fields, frame_count)?;
+                self.disk[i].copy_from_slice(&rest_words(fields, geometry.page_size())?);
+            }
+            _ => return Err(VMError::InvalidSnapshot),
+        }
+
+        Ok(())
+    }
+
+    /// First table entry reachable from the tables of a process that names a
+    /// frame or disk block past the end of memory or the disk, which a later
+    /// translation would otherwise index.
+    fn invalid_entry(&self) -> Option<EntryLocation> {
+        // Words of a table at each level that hold entries
+        let entries = (0, 1);
+        let segment_entries = (SEGMENT_PAGE_TABLE_OFFSET, SEGMENT_WORD_COUNT);
+        let levels = match self.scheme {
+            Scheme::Segmented if self.paged_segment_table => {
+                vec![entries, segment_entries, entries]
+            }
+            Scheme::Segmented => vec![segment_entries, entries],
+            Scheme::Radix { levels } => vec![entries; levels],
+            Scheme::PurePaging => vec![entries],
+            Scheme::PureSegmentation => return self.invalid_segment(),
+            Scheme::Inverted => return self.invalid_chain(),
+        };
+
+        let mut visited = HashSet::new();
+        self.address_spaces
+            .iter()
+            .flat_map(|address_space| &address_space.segment_table)
+            .find_map(|&frame| self.invalid_table(false, frame, &levels, &mut visited))
+    }
+
+    /// First entry of the table in frame or disk block `index`, or of the
+    /// tables below it, that names a frame or block that does not exist.
+    fn invalid_table(
+        &self,
+        on_disk: bool,
+        index: usize,
+        levels: &[(usize, usize)],
+        visited: &mut HashSet<(bool, usize, usize)>,
+    ) -> Option<EntryLocation> {
+        let (&(first, step), lower) = levels.split_first()?;
+        // A corrupt table may point back at one above it
+        if !visited.insert((on_disk, index, levels.len())) {
+            return None;
+        }
+
+        let words = if on_disk {
+            &self.disk[index]
+        } else {
+            &self.physical_memory[index].data
+        };
+
+        for offset in (first..words.len()).step_by(step) {
+            let entry = words[offset];
+            if entry == 0 {
+                continue;
+            }
+
+            let child = usize::try_from(entry.unsigned_abs()).ok()?;
+            if child >= self.geometry.frame_count() {
+                return Some(if on_disk {
+                    EntryLocation::Disk {
+                        block: index,
+                        offset,
+                    }
+                } else {
+                    EntryLocation::Memory(index * self.geometry.page_size() + offset)
+                });
+            }
+            if let Some(entry) = self.invalid_table(entry < 0, child, lower, visited) {
+                return Some(entry);
+            }
+        }
+
+        None
+    }
+
+    /// First segment table entry of pure segmentation whose segment runs
+    /// past the last frame or disk block.
+    fn invalid_segment(&self) -> Option<EntryLocation> {
+        let page_size = self.geometry.page_size();
+
+        self.address_spaces.iter().find_map(|address_space| {
+            (0..self.geometry.segment_count()).find_map(|s| {
+                let s = u16::try_from(s).ok()?;
+                let size_entry = address_space.segment_entry(s, SEGMENT_SIZE_OFFSET);
+                let base_entry = address_space.segment_entry(s, SEGMENT_BASE_OFFSET);
+                let base = self.physical_memory.get_word_by_address(base_entry);
+                let Ok(size) =
+                    usize::try_from(self.physical_memory.get_word_by_address(size_entry))
+                else {
+                    return Some(EntryLocation::Memory(size_entry));
+                };
+
+                // Resident segments are based at an address, others at a block
+                let end = match usize::try_from(base) {
+                    Ok(address) => (address + size).div_ceil(page_size),
+                    Err(_) => usize::try_from(base.unsigned_abs()).ok()? + size.div_ceil(page_size),
+                };
+                (base != 0 && end > self.geometry.frame_count())
+                    .then_some(EntryLocation::Memory(base_entry))
+            })
+        })
+    }
+
+    /// First anchor or chain link of the inverted table naming a frame that
+    /// does not exist.
+    fn invalid_chain(&self) -> Option<EntryLocation> {
+        let frame_count = self.geometry.frame_count();
+        let links = (0..frame_count).chain(
+            (0..frame_count)
+                .map(|frame| frame_count + frame * INVERTED_WORD_COUNT + INVERTED_NEXT_OFFSET),
+        );
+
+        links
+            .filter(|&address| {
+                usize::try_from(self.physical_memory.get_word_by_address(address))
+                    .map_or(true, |frame| frame >= frame_count)
+            })
+            .map(EntryLocation::Memory)
+            .next()
+    }
+
+    /// Applies an `inverted` or `swapped` line to the inverted page table.
+    fn load_inverted_line(&mut self, keyword: &str, mut fields: SplitWhitespace) -> VMResult<()> {
+        let geometry = self.geometry;
+        let inverted = self.inverted.as_mut().ok_or(VMError::InvalidSnapshot)?;
+
+        if keyword == "inverted" {
+            let stats = InvertedStats {
+                lookups: next(&mut fields)?,
+                probes: next(&mut fields)?,
+                ..InvertedStats::default()
+            };
+            *inverted = InvertedTable::restore(&geometry, stats);
+        } else {
+            let key = (
+                next_index(&mut fields, self.address_spaces.len())?,
+                u16::try_from(next_index(&mut fields, geometry.segment_count())?)?,
+                u16::try_from(next_index(&mut fields, geometry.pages_per_segment())?)?,
+            );
+            inverted.swap_out(key, next_index(&mut fields, geometry.frame_count())?);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        io::{protection_input::ProtectionInput, pt_input::PTInput, st_input::STInput},
+        vm::{allocator::Fit, replacement::Clock, virtual_address::VirtualAddress},
+    };
+
+    use super::*;
+
+    fn before() -> VirtualMemory {
+        let geometry = Geometry::default();
+        let st_inputs = vec![
+            STInput::new("8", "4000", "3", &geometry).expect("Failed to create STInput"),
+            STInput::new("9", "5000", "-7", &geometry).expect("Failed to create STInput"),
+        ];
+        let pt_inputs = vec![
+            PTInput::new("8", "0", "10", &geometry).expect("Failed to create PTInput"),
+            PTInput::new("8", "1", "-20", &geometry).expect("Failed to create PTInput"),
+            PTInput::new("9", "0", "13", &geometry).expect("Failed to create PTInput"),
+            PTInput::new("9", "1", "-25", &geometry).expect("Failed to create PTInput"),
+        ];
+
+        VirtualMemory::new(st_inputs, pt_inputs)
+            .expect("Failed to create VirtualMemory")
+            .with_policy(Box::new(Clock::new()))
+            .with_tlb(TlbConfig {
+                entries: 4,
+                associativity: 2,
+                replacement: TlbReplacement::Lru,
+            })
+            .expect("Failed to create TLB")
+            .with_protection(vec![ProtectionInput::new("9", "1", "rw-", &geometry)
+                .expect("Failed to create ProtectionInput")])
+    }
+
+    fn address(virtual_address: u32) -> VirtualAddress {
+        VirtualAddress::new(virtual_address, &Geometry::default())
+            .expect("Failed to create VirtualAddress")
+    }
+
+    fn save(virtual_memory: &VirtualMemory) -> String {
+        let mut snapshot = Vec::new();
+        virtual_memory
+            .save_snapshot(&mut snapshot)
+            .expect("Failed to save snapshot");
+        String::from_utf8(snapshot).expect("Snapshot is not UTF-8")
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut vm = before();
+        vm.write_word(address(2_359_818), 42)
+            .expect("Failed to write word");
+        vm.translate(address(2_097_664))
+            .expect("Failed to translate");
+
+        let snapshot = save(&vm);
+        let mut restored =
+            VirtualMemory::load_snapshot(&mut snapshot.as_bytes()).expect("Failed to load");
+        assert_eq!(save(&restored), snapshot);
+        assert_eq!(restored.read_word(address(2_359_818)), Ok(42));
+        assert_eq!(restored.permissions(9, 1), vm.permissions(9, 1));
+
+        // Both copies keep behaving the same
+        vm.read_word(address(2_359_818))
+            .expect("Failed to read word");
+        for virtual_address in [2_097_152, 2_359_306, 2_098_176] {
+            assert_eq!(
+                restored.translate(address(virtual_address)),
+                vm.translate(address(virtual_address))
+            );
+        }
+        assert_eq!(save(&restored), save(&vm));
+        assert_eq!(restored, vm);
+        assert!(!restored.demand_zero());
+
+        let snapshot = save(&before().with_demand_zero(true));
+        let restored =
+            VirtualMemory::load_snapshot(&mut snapshot.as_bytes()).expect("Failed to load");
+        assert!(restored.demand_zero());
+
+        let mut vm = before()
+            .with_paged_segment_table()
+            .expect("Failed to page segment table");
+        vm.translate(address(2_359_306))
+            .expect("Failed to translate");
+        let snapshot = save(&vm);
+        let mut restored =
+            VirtualMemory::load_snapshot(&mut snapshot.as_bytes()).expect("Failed to load");
+        assert!(restored.paged_segment_table());
+        assert_eq!(save(&restored), snapshot);
+        assert_eq!(
+            restored.translate(address(2_097_162)),
+            vm.translate(address(2_097_162))
+        );
+
+        let vm = VirtualMemory::with_scheme(
+            Geometry::default(),
+            Scheme::Radix { levels: 3 },
+            vec![],
+            vec![],
+        )
+        .expect("Failed to init");
+        let snapshot = save(&vm);
+        let restored =
+            VirtualMemory::load_snapshot(&mut snapshot.as_bytes()).expect("Failed to load");
+        assert_eq!(restored.scheme(), Scheme::Radix { levels: 3 });
+        assert_eq!(save(&restored), snapshot);
+
+        let geometry = Geometry::default();
+        let pt = ["8 0 10", "8 1 -20", "9 0 13"]
+            .iter()
+            .map(|entry| {
+                let fields: Vec<&str> = entry.split(' ').collect();
+                PTInput::new(fields[0], fields[1], fields[2], &geometry)
+            })
+            .collect::<VMResult<Vec<_>>>()
+            .expect("Failed to create PTInput");
+        let mut vm = VirtualMemory::with_scheme(geometry, Scheme::Inverted, vec![], pt)
+            .expect("Failed to init");
+        vm.write_word(address(2_097_162), 42)
+            .expect("Failed to write word");
+        vm.evict(10).expect("Failed to evict");
+        let snapshot = save(&vm);
+        let mut restored =
+            VirtualMemory::load_snapshot(&mut snapshot.as_bytes()).expect("Failed to load");
+        assert_eq!(restored.scheme(), Scheme::Inverted);
+        assert_eq!(save(&restored), snapshot);
+        assert_eq!(restored.inverted_stats(), vm.inverted_stats());
+        assert_eq!(restored.read_word(address(2_097_162)), Ok(42));
+        assert_eq!(vm.read_word(address(2_097_162)), Ok(42));
+        assert_eq!(
+            restored.translate(address(2_097_674)),
+            vm.translate(address(2_097_674))
+        );
+    }
+
+    #[test]
+    fn round_trip_fork() {
+        let mut vm = before();
+        let child = vm.fork().expect("Failed to fork");
+        vm.switch_to(child).expect("Failed to switch");
+        vm.fork().expect("Failed to fork");
+
+        let snapshot = save(&vm);
+        let mut restored =
+            VirtualMemory::load_snapshot(&mut snapshot.as_bytes()).expect("Failed to load");
+        assert_eq!(restored, vm);
+
+        // The restored pages are still shared by all three processes
+        restored
+            .write_word(address(2_097_162), 42)
+            .expect("Failed to write word");
+        vm.write_word(address(2_097_162), 42)
+            .expect("Failed to write word");
+        assert_eq!(restored, vm);
+    }
+
+    #[test]
+    fn round_trip_pure_schemes() {
+        let geometry = Geometry::default();
+        for scheme in [Scheme::PureSegmentation, Scheme::PurePaging] {
+            let st = vec![
+                STInput::new("8", "4000", "3", &geometry).expect("Failed to create STInput"),
+                STInput::new("9", "5000", "-7", &geometry).expect("Failed to create STInput"),
+            ];
+            let pt = vec![
+                PTInput::new("8", "0", "10", &geometry).expect("Failed to create PTInput"),
+                PTInput::new("9", "0", "-20", &geometry).expect("Failed to create PTInput"),
+            ];
+            let mut vm = VirtualMemory::with_scheme(geometry, scheme, st, pt)
+                .expect("Failed to init")
+                .with_fit(Fit::Next);
+            vm.write_word(address(2_359_306), 42)
+                .expect("Failed to write word");
+
+            let snapshot = save(&vm);
+            let mut restored =
+                VirtualMemory::load_snapshot(&mut snapshot.as_bytes()).expect("Failed to load");
+            assert_eq!(restored.scheme(), scheme);
+            assert_eq!(restored.fit(), Fit::Next);
+            assert_eq!(save(&restored), snapshot);
+            assert_eq!(restored.read_word(address(2_359_306)), Ok(42));
+            assert_eq!(
+                restored.translate(address(2_097_162)),
+                vm.translate(address(2_097_162))
+            );
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let snapshot = save(&before());
+        let load = |snapshot: &str| VirtualMemory::load_snapshot(&mut snapshot.as_bytes());
+
+        assert_eq!(
+            load(&snapshot.replacen("vm-snapshot 1", "vm-snapshot 2", 1)).map(|_| ()),
+            Err(VMError::InvalidSnapshot)
+        );
+        assert_eq!(
+            load(snapshot.trim_end().trim_end_matches(END)).map(|_| ()),
+            Err(VMError::InvalidSnapshot)
+        );
+        assert_eq!(
+            load(&format!("{snapshot}active 0\n")).map(|_| ()),
+            Err(VMError::InvalidSnapshot)
+        );
+        assert_eq!(
+            load(&snapshot.replacen("active 0", "active 1", 1)).map(|_| ()),
+            Err(VMError::InvalidSnapshot)
+        );
+        assert_eq!(
+            load(&snapshot.replacen("frame 3 1 pt", "frame 3 1 table", 1)).map(|_| ()),
+            Err(VMError::InvalidSnapshot)
+        );
+        assert_eq!(
+            load(&snapshot.replacen("\nend", "\ndata 3 1 2 3\nend", 1)).map(|_| ()),
+            Err(VMError::InvalidSnapshot)
+        );
+        for stats in [
+            "stats 0 0 0 0 0 0 0 x",
+            "stats 0 0 0 0 0 0 0",
+            "stats 0 0 0 0 0 0 0 0 0",
+        ] {
+            assert_eq!(
+                load(&snapshot.replacen("stats 0 0 0 0 0 0 0 0", stats, 1)).map(|_| ()),
+                Err(VMError::InvalidSnapshot)
+            );
+        }
+    }
+    #[test]
+    fn invalid_entries() {
+        let snapshot = save(&before());
+        let line = |prefix: &str| {
+            snapshot
+                .lines()
+                .position(|line| line.starts_with(prefix))
+                .expect("Missing snapshot line")
+                + 1
+        };
+
+        // A segment table, page table and on-disk page table entry past the
+        // last frame or block
+        for (from, to, line, token) in [
+            ("4000 3 ", "4000 1024 ", line("data 0 "), 20),
+            ("data 3 10 ", "data 3 1024 ", line("data 3 "), 3),
+            ("block 7 13 -25 ", "block 7 13 -1024 ", line("block 7 "), 4),
+        ] {
+            let error =
+                VirtualMemory::load_snapshot(&mut snapshot.replacen(from, to, 1).as_bytes())
+                    .expect_err("Corrupt entry loaded")
+                    .in_file("snapshot.txt");
+            assert_eq!(
+                error,
+                VMError::Parse {
+                    file: "snapshot.txt".into(),
+                    line,
+                    token,
+                    error: Box::new(VMError::InvalidFrame),
+                }
+            );
+        }
+    }
+}